@@ -0,0 +1,185 @@
+use crate::Clock;
+use crate::Instant;
+use crate::Rate;
+use crate::SystemClock;
+use std::time::Duration;
+
+/// How far the period may drift from the target's, either way.
+const MAX_SCALE: u32 = 8;
+
+/// A fixed window limiter letting `target.count` logs through per window, whose period adapts to
+/// keep the emitted logs near the `target` rate rather than staying at `target.per`.
+///
+/// A window only ends with the first log after it, so a flood arriving at a modest rate loses the
+/// time until that log from every window and gets less than the target. Each time a saturated
+/// window ends, the period is therefore scaled by how far the rate emitted over the window's
+/// actual span was from the target: shortened when under, lengthened when over. Windows that
+/// didn't use up their budget leave it as is, as the logs rather than the limiter set their rate.
+/// Each step at most halves or doubles the period, and it stays within a factor of 8 of
+/// `target.per`.
+pub struct AdaptivePeriodLimiter<C: Clock = SystemClock> {
+    clock: C,
+    target: Rate,
+    period: Duration,
+    window_start: Option<Instant>,
+    count: usize,
+    suppressed: usize,
+}
+
+impl AdaptivePeriodLimiter {
+    pub fn new(target: Rate) -> Self {
+        Self::with_clock(SystemClock, target)
+    }
+}
+
+impl<C: Clock> AdaptivePeriodLimiter<C> {
+    /// # Panics
+    ///
+    /// If `target.per` is zero.
+    pub fn with_clock(clock: C, target: Rate) -> Self {
+        assert!(
+            !target.per.is_zero(),
+            "The target's period needs to be non-zero"
+        );
+        Self {
+            clock,
+            target,
+            period: target.per,
+            window_start: None,
+            count: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// The current period.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Logs suppressed in the current window.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let now = self.clock.now();
+        let elapsed = self
+            .window_start
+            .map(|window_start| now.duration_since(window_start));
+        match elapsed {
+            Some(elapsed) if elapsed <= self.period => {}
+            _ => {
+                if let Some(elapsed) = elapsed {
+                    #[cfg(feature = "warning-messages")]
+                    if self.suppressed > 0 {
+                        crate::meta::warn_ignored(
+                            self.suppressed,
+                            elapsed,
+                            None,
+                            format_args!(
+                                "Ignored {} logs since {} ago. Starting to log again...",
+                                self.suppressed,
+                                crate::meta::FriendlyDuration(elapsed)
+                            ),
+                        );
+                    }
+                    if self.suppressed > 0 {
+                        self.adapt(elapsed);
+                    }
+                }
+                self.window_start = Some(now);
+                self.count = 0;
+                self.suppressed = 0;
+            }
+        }
+
+        if self.count < self.target.count {
+            log();
+            self.count += 1;
+        } else {
+            self.suppressed += 1;
+        }
+    }
+
+    /// Scales the period by the ratio of the rate emitted over the window's `span` to the target.
+    /// A target of 0 logs, or a span of zero, leaves it as is.
+    fn adapt(&mut self, span: Duration) {
+        let emitted_rate = self.count as f64 / span.as_secs_f64();
+        let target_rate = self.target.count as f64 / self.target.per.as_secs_f64();
+        let scale = emitted_rate / target_rate;
+        let scale = if scale.is_finite() {
+            scale.clamp(0.5, 2.0)
+        } else {
+            1.0
+        };
+        let period =
+            Duration::try_from_secs_f64(self.period.as_secs_f64() * scale).unwrap_or(Duration::MAX);
+        self.period = period.clamp(
+            self.target.per / MAX_SCALE,
+            self.target.per.saturating_mul(MAX_SCALE),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AdaptivePeriodLimiter;
+    use crate::ManualClock;
+    use crate::Rate;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn output_stays_near_the_target_as_the_input_rate_varies() {
+        let clock = ManualClock::new();
+        let target = Rate::new(10, Duration::from_secs(1));
+        let mut limiter = AdaptivePeriodLimiter::with_clock(clock.clone(), target);
+        let emitted = Cell::new(0_usize);
+        let mut per_phase = Vec::new();
+        // Logs per second for 20s each, counting the emitted logs of the last 10s.
+        for calls_per_second in [1000, 20, 5, 200] {
+            for second in 0..20 {
+                if second == 10 {
+                    emitted.set(0);
+                }
+                for _ in 0..calls_per_second {
+                    limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+                    clock.advance(Duration::from_secs(1) / calls_per_second);
+                }
+            }
+            per_phase.push(emitted.get());
+        }
+
+        // At 20 logs per second a fixed 1s window only emits 90 logs per 10s here, as each window
+        // loses the time until the next log. Below the target everything passes.
+        assert!(
+            per_phase
+                .iter()
+                .zip([100, 100, 50, 100])
+                .all(|(&emitted, expected)| emitted.abs_diff(expected) <= 3),
+            "{per_phase:?}"
+        );
+    }
+
+    #[test]
+    fn degenerate_targets_keep_their_period() {
+        let clock = ManualClock::new();
+        let mut limiter =
+            AdaptivePeriodLimiter::with_clock(clock.clone(), Rate::new(0, Duration::from_secs(1)));
+        for _ in 0..3 {
+            limiter.log_maybe(|| panic!("a target of 0 logs nothing"));
+            clock.advance(Duration::from_secs(2));
+        }
+        assert_eq!(limiter.period(), Duration::from_secs(1));
+
+        // Eight times this period doesn't fit in a `Duration`.
+        let per = Duration::from_secs(u64::MAX / 6);
+        let mut limiter = AdaptivePeriodLimiter::with_clock(clock.clone(), Rate::new(1, per));
+        limiter.log_maybe(|| {});
+        limiter.log_maybe(|| {});
+        clock.advance(per * 2);
+        limiter.log_maybe(|| {});
+        // Halved, up to the precision of an `f64`.
+        assert!(limiter.period().abs_diff(per / 2) < per / 1_000_000);
+    }
+}
@@ -0,0 +1,161 @@
+use crate::Clock;
+use crate::Instant;
+use crate::SystemClock;
+use std::time::Duration;
+use std::time::SystemTime;
+
+/// A fixed window limiter whose windows are aligned to whole multiples of `period` since an
+/// epoch, rather than starting with the first log. Every process using the same epoch and period
+/// buckets logs into identical windows, e.g. so the replicas of a fleet can be compared, without
+/// any coordination beyond roughly synchronised clocks.
+///
+/// The epoch defaults to the Unix epoch, so e.g. one minute windows start on the minute.
+pub struct AlignedLimiter<C: Clock = SystemClock> {
+    clock: C,
+    /// A reading of `clock` and the wall-clock time it corresponds to.
+    anchor: (Instant, SystemTime),
+    epoch: SystemTime,
+    max_per_time: usize,
+    period: Duration,
+    window: u128,
+    count: usize,
+    suppressed: usize,
+}
+
+impl AlignedLimiter {
+    pub fn new(max_per_time: usize, period: Duration) -> Self {
+        Self::with_clock(SystemClock, SystemTime::now(), max_per_time, period)
+    }
+}
+
+impl<C: Clock> AlignedLimiter<C> {
+    /// Uses `clock`, whose current reading corresponds to the wall-clock time `now`.
+    pub fn with_clock(clock: C, now: SystemTime, max_per_time: usize, period: Duration) -> Self {
+        let mut limiter = Self {
+            anchor: (clock.now(), now),
+            clock,
+            epoch: SystemTime::UNIX_EPOCH,
+            max_per_time,
+            period,
+            window: 0,
+            count: 0,
+            suppressed: 0,
+        };
+        limiter.window = limiter.current_window();
+        limiter
+    }
+
+    /// Aligns the windows to `epoch` instead of the Unix epoch. Windows before the epoch are
+    /// treated as the first one.
+    pub fn with_epoch(mut self, epoch: SystemTime) -> Self {
+        self.epoch = epoch;
+        self.window = self.current_window();
+        self
+    }
+
+    /// When the window the next log falls into starts.
+    pub fn window_start(&self) -> SystemTime {
+        let nanos = self.current_window() * self.period.as_nanos();
+        let since_epoch = Duration::new(
+            u64::try_from(nanos / 1_000_000_000).unwrap_or(u64::MAX),
+            (nanos % 1_000_000_000) as u32,
+        );
+        self.epoch.checked_add(since_epoch).unwrap_or(self.epoch)
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let window = self.current_window();
+        if window != self.window {
+            #[cfg(feature = "warning-messages")]
+            if self.suppressed > 0 {
+                crate::meta::warn_ignored(
+                    self.suppressed,
+                    self.period,
+                    None,
+                    format_args!(
+                        "Ignored {} logs over the last aligned window of {}. Starting to log again...",
+                        self.suppressed,
+                        crate::meta::FriendlyDuration(self.period)
+                    ),
+                );
+            }
+            self.window = window;
+            self.count = 0;
+            self.suppressed = 0;
+        }
+
+        if self.count < self.max_per_time {
+            log();
+            self.count += 1;
+            #[cfg(feature = "warning-messages")]
+            if self.count == self.max_per_time {
+                crate::meta::warn_threshold(format_args!(
+                    "Hit logging threshold! Starting to ignore the previous log until the next aligned window of {}",
+                    crate::meta::FriendlyDuration(self.period)
+                ));
+            }
+        } else {
+            self.suppressed += 1;
+        }
+    }
+
+    fn current_window(&self) -> u128 {
+        let (anchor_instant, anchor_time) = self.anchor;
+        let elapsed = self.clock.now().duration_since(anchor_instant);
+        let now = anchor_time.checked_add(elapsed).unwrap_or(anchor_time);
+        let since_epoch = now.duration_since(self.epoch).unwrap_or(Duration::ZERO);
+        since_epoch.as_nanos() / self.period.as_nanos().max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AlignedLimiter;
+    use crate::ManualClock;
+    use std::cell::Cell;
+    use std::time::Duration;
+    use std::time::SystemTime;
+
+    #[test]
+    fn limiters_sharing_an_epoch_share_window_boundaries() {
+        let epoch = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let period = Duration::from_secs(10);
+        // Two replicas started at different times, 3s and 17s after the epoch.
+        let (first_clock, second_clock) = (ManualClock::new(), ManualClock::new());
+        let mut first = AlignedLimiter::with_clock(
+            first_clock.clone(),
+            epoch + Duration::from_secs(3),
+            2,
+            period,
+        )
+        .with_epoch(epoch);
+        let mut second = AlignedLimiter::with_clock(
+            second_clock.clone(),
+            epoch + Duration::from_secs(17),
+            2,
+            period,
+        )
+        .with_epoch(epoch);
+        // Bring the first replica to the same wall-clock time as the second.
+        first_clock.advance(Duration::from_secs(14));
+
+        let emitted = (Cell::new(0), Cell::new(0));
+        let mut emissions = Vec::new();
+        for _ in 0..6 {
+            assert_eq!(first.window_start(), second.window_start());
+            emitted.0.set(0);
+            emitted.1.set(0);
+            for _ in 0..5 {
+                first.log_maybe(|| emitted.0.set(emitted.0.get() + 1));
+                second.log_maybe(|| emitted.1.set(emitted.1.get() + 1));
+            }
+            emissions.push((emitted.0.get(), emitted.1.get()));
+            first_clock.advance(Duration::from_secs(2));
+            second_clock.advance(Duration::from_secs(2));
+        }
+
+        // 17s, 19s, then 21s starts the window at 20s.
+        assert_eq!(emissions, [(2, 2), (0, 0), (2, 2), (0, 0), (0, 0), (0, 0)]);
+        assert_eq!(first.window_start(), epoch + Duration::from_secs(20));
+    }
+}
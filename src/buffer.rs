@@ -0,0 +1,237 @@
+//! Keeps the most recently suppressed logs below the error level and dumps them when an
+//! `error_limit!` or `error_limit_global!` call site emits, giving context around the error.
+//!
+//! Buffering is off until [`set_capacity`] is called with a non-zero capacity. The buffer is shared
+//! by every call site and evicts the oldest log once full. Only the thread-local and global macros
+//! feed it, as they know the message of a suppressed log.
+//!
+//! Separately, [`set_overflow_sink`] passes every suppressed message to a sink of its own, e.g. a
+//! low-volume forensics file, at a bounded rate.
+
+use crate::Clock;
+use crate::Instant;
+use crate::LogContext;
+use crate::SystemClock;
+use log::Level;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+struct BufferedLog {
+    level: Level,
+    target: String,
+    message: String,
+}
+
+static BUFFER: Mutex<VecDeque<BufferedLog>> = Mutex::new(VecDeque::new());
+// Lets the macros skip taking the lock when buffering is off.
+static CAPACITY: AtomicUsize = AtomicUsize::new(0);
+
+struct OverflowSink {
+    sink: Arc<dyn Fn(&str) + Send + Sync>,
+    clock: Box<dyn Clock + Send + Sync>,
+    max_per_time: usize,
+    period: Duration,
+    window_start: Option<Instant>,
+    count: usize,
+}
+
+static OVERFLOW_SINK: Mutex<Option<OverflowSink>> = Mutex::new(None);
+// Lets the macros skip taking the lock, and formatting, when there's no sink.
+static ANY_OVERFLOW_SINK: AtomicBool = AtomicBool::new(false);
+
+/// Buffers up to `capacity` suppressed logs, or none if it's zero, discarding any over capacity.
+pub fn set_capacity(capacity: usize) {
+    let mut buffer = BUFFER.lock().unwrap();
+    CAPACITY.store(capacity, Ordering::Relaxed);
+    let excess = buffer.len().saturating_sub(capacity);
+    buffer.drain(..excess);
+}
+
+/// Passes the message of every suppressed log to `sink`, e.g. to write them to a ring file for
+/// forensics, but at most `max_per_time` per `period`, dropping the rest. Replaces any previous
+/// sink.
+///
+/// The sink is called without holding any lock, so it may log through the limited macros itself.
+pub fn set_overflow_sink(
+    max_per_time: usize,
+    period: Duration,
+    sink: impl Fn(&str) + Send + Sync + 'static,
+) {
+    set_overflow_sink_with_clock(SystemClock, max_per_time, period, sink);
+}
+
+/// Like [`set_overflow_sink`], but timing the `period` by `clock`.
+pub fn set_overflow_sink_with_clock(
+    clock: impl Clock + Send + Sync + 'static,
+    max_per_time: usize,
+    period: Duration,
+    sink: impl Fn(&str) + Send + Sync + 'static,
+) {
+    *OVERFLOW_SINK.lock().unwrap() = Some(OverflowSink {
+        sink: Arc::new(sink),
+        clock: Box::new(clock),
+        max_per_time,
+        period,
+        window_start: None,
+        count: 0,
+    });
+    ANY_OVERFLOW_SINK.store(true, Ordering::Relaxed);
+}
+
+/// Stops passing suppressed logs to the sink set by [`set_overflow_sink`].
+pub fn clear_overflow_sink() {
+    *OVERFLOW_SINK.lock().unwrap() = None;
+    ANY_OVERFLOW_SINK.store(false, Ordering::Relaxed);
+}
+
+/// Dumps the buffered logs if `level` is an error, before the error itself is logged.
+#[doc(hidden)]
+pub fn on_emit(level: Level) {
+    if level != Level::Error || CAPACITY.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    let buffered: Vec<_> = BUFFER.lock().unwrap().drain(..).collect();
+    if buffered.is_empty() {
+        return;
+    }
+    #[cfg(feature = "warning-messages")]
+    crate::meta::warn_dumping(
+        buffered.len(),
+        format_args!(
+            "Dumping {} suppressed logs leading up to the error:",
+            buffered.len()
+        ),
+    );
+    for log in buffered {
+        log::log!(target: &log.target, log.level, "{}", log.message);
+    }
+}
+
+/// Buffers the message of a suppressed log below the error level, and passes it to the overflow
+/// sink if there is one.
+#[doc(hidden)]
+pub fn on_suppress(context: &LogContext, message: impl FnOnce() -> String) {
+    let capacity = CAPACITY.load(Ordering::Relaxed);
+    let buffered = context.level != Level::Error && capacity > 0;
+    let overflowed = ANY_OVERFLOW_SINK.load(Ordering::Relaxed);
+    if !buffered && !overflowed {
+        return;
+    }
+    let message = message();
+    if overflowed {
+        overflow(&message);
+    }
+    if !buffered {
+        return;
+    }
+    let mut buffer = BUFFER.lock().unwrap();
+    if buffer.len() >= capacity {
+        buffer.pop_front();
+    }
+    buffer.push_back(BufferedLog {
+        level: context.level,
+        target: context.target.to_owned(),
+        message,
+    });
+}
+
+fn overflow(message: &str) {
+    let sink = {
+        let mut overflow_sink = OVERFLOW_SINK.lock().unwrap();
+        let Some(overflow_sink) = &mut *overflow_sink else {
+            return;
+        };
+        let now = overflow_sink.clock.now();
+        if overflow_sink
+            .window_start
+            .is_none_or(|window_start| now.duration_since(window_start) > overflow_sink.period)
+        {
+            overflow_sink.window_start = Some(now);
+            overflow_sink.count = 0;
+        }
+        if overflow_sink.count >= overflow_sink.max_per_time {
+            return;
+        }
+        overflow_sink.count += 1;
+        overflow_sink.sink.clone()
+    };
+    sink(message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clear_overflow_sink;
+    use super::set_capacity;
+    use super::set_overflow_sink;
+    use crate::error_limit;
+    use crate::info_limit;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    #[test]
+    fn suppressed_messages_reach_the_overflow_sink() {
+        let overflowed = Arc::new(Mutex::new(Vec::new()));
+        let sink = overflowed.clone();
+        // Generous, as suppressions of tests running in parallel reach the sink too.
+        set_overflow_sink(1000, Duration::from_secs(60), move |message| {
+            sink.lock().unwrap().push(message.to_owned())
+        });
+        for i in 0..4 {
+            info_limit!(1, Duration::from_secs(60), "overflow {i}");
+        }
+        clear_overflow_sink();
+
+        let overflowed = overflowed.lock().unwrap();
+        let ours: Vec<_> = overflowed
+            .iter()
+            .filter(|message| message.starts_with("overflow "))
+            .collect();
+        assert_eq!(ours, ["overflow 1", "overflow 2", "overflow 3"]);
+    }
+
+    #[test]
+    fn error_dumps_the_suppressed_logs() {
+        crate::testing_logger::setup();
+        set_capacity(3);
+        let period = Duration::from_secs(60);
+        for i in 0..5 {
+            info_limit!(1, period, "info {i}");
+        }
+        error_limit!(1, period, "error");
+        set_capacity(0);
+
+        crate::testing_logger::validate(|captured_logs| {
+            let levels: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.level != log::Level::Warn)
+                .map(|log| log.level)
+                .collect();
+            // The first info, then the last 3 of the 4 suppressed ones, then the error.
+            assert_eq!(
+                levels,
+                [
+                    log::Level::Info,
+                    log::Level::Info,
+                    log::Level::Info,
+                    log::Level::Info,
+                    log::Level::Error
+                ]
+            );
+            #[cfg(feature = "warning-messages")]
+            {
+                let bodies: Vec<_> = captured_logs
+                    .iter()
+                    .filter(|log| log.level != log::Level::Warn)
+                    .map(|log| log.body.as_str())
+                    .collect();
+                assert_eq!(bodies, ["info 0", "info 2", "info 3", "info 4", "error"]);
+            }
+        });
+    }
+}
@@ -0,0 +1,150 @@
+use crate::Clock;
+use crate::Instant;
+use crate::SystemClock;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write;
+use std::hash::Hasher;
+use std::time::Duration;
+
+/// Feeds formatted text straight into a hasher, sparing an allocation for the message.
+struct HashWriter(DefaultHasher);
+
+impl Write for HashWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0.write(s.as_bytes());
+        Ok(())
+    }
+}
+
+/// A limiter on the number of distinct messages rather than on the number of logs, for floods
+/// of many different messages each logged once, e.g. one per failing request ID.
+///
+/// Only the first `max_distinct` distinct messages of each `period` long window are emitted, and
+/// any new distinct message after them is suppressed. Repeats of an already emitted message are
+/// let through, so combine it with a count based limiter to bound those too. Messages are told
+/// apart by a 64 bit hash of their formatted text, so two messages may collide, in which case
+/// the second counts as a repeat of the first.
+pub struct CardinalityLimiter<C: Clock = SystemClock> {
+    clock: C,
+    max_distinct: usize,
+    period: Duration,
+    window_start: Option<Instant>,
+    seen: HashSet<u64>,
+    suppressed: usize,
+}
+
+impl CardinalityLimiter {
+    pub fn new(max_distinct: usize, period: Duration) -> Self {
+        Self::with_clock(SystemClock, max_distinct, period)
+    }
+}
+
+impl<C: Clock> CardinalityLimiter<C> {
+    pub fn with_clock(clock: C, max_distinct: usize, period: Duration) -> Self {
+        Self {
+            clock,
+            max_distinct,
+            period,
+            window_start: None,
+            seen: HashSet::new(),
+            suppressed: 0,
+        }
+    }
+
+    /// The number of distinct messages emitted in the current window.
+    pub fn distinct(&self) -> usize {
+        self.seen.len()
+    }
+
+    /// Calls `log` unless `message` is a new distinct message beyond the budget of the window.
+    pub fn log_maybe(&mut self, message: impl fmt::Display, log: impl Fn()) {
+        let now = self.clock.now();
+        let elapsed = self
+            .window_start
+            .map(|window_start| now.duration_since(window_start));
+        match elapsed {
+            Some(elapsed) if elapsed <= self.period => {}
+            _ => {
+                #[cfg(feature = "warning-messages")]
+                if let (Some(elapsed), true) = (elapsed, self.suppressed > 0) {
+                    crate::meta::warn_ignored(
+                        self.suppressed,
+                        elapsed,
+                        None,
+                        format_args!(
+                            "Ignored {} logs of new distinct messages since {} ago. Starting to log again...",
+                            self.suppressed,
+                            crate::meta::FriendlyDuration(elapsed)
+                        ),
+                    );
+                }
+                self.window_start = Some(now);
+                self.seen.clear();
+                self.suppressed = 0;
+            }
+        }
+
+        let mut hasher = HashWriter(DefaultHasher::new());
+        // Writing to a hasher can't fail, only a `Display` impl returning an error can.
+        let _ = write!(hasher, "{message}");
+        let hash = hasher.0.finish();
+        if self.seen.contains(&hash) {
+            log();
+        } else if self.seen.len() < self.max_distinct {
+            self.seen.insert(hash);
+            log();
+            #[cfg(feature = "warning-messages")]
+            if self.seen.len() == self.max_distinct {
+                crate::meta::warn_threshold(format_args!(
+                    "Hit distinct message threshold! Starting to ignore new messages for {}",
+                    crate::meta::FriendlyDuration(self.period)
+                ));
+            }
+        } else {
+            self.suppressed += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CardinalityLimiter;
+    use crate::ManualClock;
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    use std::time::Duration;
+
+    #[test]
+    fn only_the_first_distinct_messages_emit() {
+        let clock = ManualClock::new();
+        let period = Duration::from_secs(1);
+        let mut limiter = CardinalityLimiter::with_clock(clock.clone(), 3, period);
+        let emitted = RefCell::new(Vec::new());
+
+        for request in 0..100 {
+            for _ in 0..5 {
+                let message = format!("Request {} failed", request % 20);
+                limiter.log_maybe(&message, || emitted.borrow_mut().push(message.clone()));
+            }
+        }
+        let distinct: HashSet<_> = emitted.borrow().iter().cloned().collect();
+        let expected: HashSet<_> = (0..3)
+            .map(|request| format!("Request {request} failed"))
+            .collect();
+        assert_eq!(distinct, expected);
+        // Repeats of the emitted messages are let through.
+        assert_eq!(emitted.borrow().len(), 5 * 5 * 3);
+        assert_eq!(limiter.distinct(), 3);
+
+        // A new window admits new distinct messages.
+        clock.advance(period * 2);
+        emitted.borrow_mut().clear();
+        for request in 10..20 {
+            let message = format!("Request {request} failed");
+            limiter.log_maybe(&message, || emitted.borrow_mut().push(message.clone()));
+        }
+        assert_eq!(emitted.borrow().len(), 3);
+    }
+}
@@ -0,0 +1,100 @@
+//! Budgets looked up by category, e.g. `"network"`, `"disk"` or `"auth"`, from a table set once
+//! for the whole program, keeping the limits of a library's many call sites in one place.
+//!
+//! [`category_limit!`](crate::category_limit) resolves its limits from the table on every call,
+//! with categories missing from it using the table's default. Each call site still has a budget
+//! of its own, limited per thread like `*_limit!`; use a [`policy`](crate::policy) to have
+//! call sites share one.
+
+use crate::LimitSpec;
+use std::sync::RwLock;
+use std::time::Duration;
+
+type Table = &'static [(&'static str, LimitSpec)];
+
+/// The limits of every category until [`set_table`] is called.
+const UNSET_DEFAULT: LimitSpec = LimitSpec {
+    max_per_time: 10,
+    period: Duration::from_secs(1),
+};
+
+static TABLE: RwLock<(Table, LimitSpec)> = RwLock::new((&[], UNSET_DEFAULT));
+
+/// Looks up the limits of each category in `table`, giving those missing from it `default`.
+/// Until set, every category gets 10 logs per second. Replaces any previous table.
+///
+/// ```
+/// use log_limit::LimitSpec;
+/// use std::time::Duration;
+///
+/// static CATEGORIES: &[(&str, LimitSpec)] = &[
+///     ("network", LimitSpec { max_per_time: 5, period: Duration::from_secs(1) }),
+///     ("disk", LimitSpec { max_per_time: 1, period: Duration::from_secs(60) }),
+/// ];
+///
+/// let default = LimitSpec { max_per_time: 10, period: Duration::from_secs(1) };
+/// log_limit::category::set_table(CATEGORIES, default);
+/// log_limit::category_limit!("network", log::Level::Warn, "Connection reset");
+/// ```
+pub fn set_table(table: Table, default: LimitSpec) {
+    *TABLE.write().unwrap() = (table, default);
+}
+
+/// The limits of `category`.
+pub fn resolve(category: &str) -> LimitSpec {
+    let (table, default) = *TABLE.read().unwrap();
+    table
+        .iter()
+        .find(|(name, _)| *name == category)
+        .map_or(default, |(_, spec)| *spec)
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "warning-messages")]
+    use super::set_table;
+    #[cfg(feature = "warning-messages")]
+    use crate::LimitSpec;
+    #[cfg(feature = "warning-messages")]
+    use std::time::Duration;
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn categories_use_their_budget_from_the_table() {
+        crate::testing_logger::setup();
+        static TABLE: [(&str, LimitSpec); 2] = [
+            (
+                "network",
+                LimitSpec {
+                    max_per_time: 2,
+                    period: Duration::from_secs(60),
+                },
+            ),
+            (
+                "disk",
+                LimitSpec {
+                    max_per_time: 4,
+                    period: Duration::from_secs(60),
+                },
+            ),
+        ];
+        let default = LimitSpec {
+            max_per_time: 1,
+            period: Duration::from_secs(60),
+        };
+        set_table(&TABLE, default);
+        for _ in 0..10 {
+            crate::category_limit!("network", log::Level::Info, "category network");
+            crate::category_limit!("disk", log::Level::Info, "category disk");
+            crate::category_limit!("auth", log::Level::Info, "category auth");
+        }
+
+        crate::testing_logger::validate(|captured_logs| {
+            let count = |body| captured_logs.iter().filter(|log| log.body == body).count();
+            assert_eq!(count("category network"), 2);
+            assert_eq!(count("category disk"), 4);
+            // Not in the table, so it gets the default.
+            assert_eq!(count("category auth"), 1);
+        });
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// The instant type used throughout the crate. With the `wasm` feature this comes from
+/// [web_time](https://docs.rs/web-time), which works in browsers where `std::time::Instant`
+/// panics, and is simply a re-export of `std::time::Instant` on every other platform.
+#[cfg(feature = "wasm")]
+pub use web_time::Instant;
+
+/// The instant type used throughout the crate.
+#[cfg(not(feature = "wasm"))]
+pub use std::time::Instant;
+
+/// A source of time for the rate limiters.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The default clock, reading the platform's monotonic clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when advanced by hand. Clones share the same time, so one handle can
+/// be given to a limiter while another is used to drive it.
+#[derive(Clone, Debug)]
+pub struct ManualClock {
+    start: Instant,
+    elapsed: Arc<Mutex<Duration>>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            elapsed: Arc::new(Mutex::new(Duration::ZERO)),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.elapsed.lock().unwrap() += duration;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.elapsed.lock().unwrap()
+    }
+}
@@ -0,0 +1,117 @@
+use crate::Clock;
+use crate::Instant;
+use crate::Rate;
+use crate::SystemClock;
+use std::time::Duration;
+
+/// Limits to a `target` rate by an exponential moving average of the rate of emitted logs rather
+/// than by windows, so there are no window edges for logs to bunch up at. Each log is let through
+/// while the average is below the target, and the average forgets older logs with a time constant
+/// of `smoothing`: a longer one tolerates longer bursts, a shorter one reacts faster.
+///
+/// Under sustained overload the output settles at the target rate, spread evenly rather than in a
+/// burst at the start of each window. Unlike the window based limiters it logs no warnings, as
+/// there's no window to summarise.
+pub struct EmaLimiter<C: Clock = SystemClock> {
+    clock: C,
+    /// The target in logs per second.
+    target: f64,
+    smoothing: Duration,
+    /// The exponentially decayed number of emitted logs, i.e. the average rate times `smoothing`.
+    level: f64,
+    updated_at: Option<Instant>,
+    suppressed: usize,
+}
+
+impl EmaLimiter {
+    pub fn new(target: Rate, smoothing: Duration) -> Self {
+        Self::with_clock(SystemClock, target, smoothing)
+    }
+}
+
+impl<C: Clock> EmaLimiter<C> {
+    /// # Panics
+    ///
+    /// If `smoothing` is zero.
+    pub fn with_clock(clock: C, target: Rate, smoothing: Duration) -> Self {
+        assert!(!smoothing.is_zero(), "The smoothing needs to be non-zero");
+        Self {
+            clock,
+            target: target.count as f64 / target.per.as_secs_f64(),
+            smoothing,
+            level: 0.0,
+            updated_at: None,
+            suppressed: 0,
+        }
+    }
+
+    /// The estimated rate of emitted logs, in logs per second.
+    pub fn rate(&self) -> f64 {
+        self.decayed_level(self.clock.now()) / self.smoothing.as_secs_f64()
+    }
+
+    /// Logs suppressed so far.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let now = self.clock.now();
+        self.level = self.decayed_level(now);
+        self.updated_at = Some(now);
+        if self.level < self.target * self.smoothing.as_secs_f64() {
+            log();
+            self.level += 1.0;
+        } else {
+            self.suppressed += 1;
+        }
+    }
+
+    fn decayed_level(&self, now: Instant) -> f64 {
+        let Some(updated_at) = self.updated_at else {
+            return self.level;
+        };
+        let elapsed = now.duration_since(updated_at).as_secs_f64();
+        self.level * (-elapsed / self.smoothing.as_secs_f64()).exp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EmaLimiter;
+    use crate::ManualClock;
+    use crate::Rate;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn output_converges_to_the_target_after_a_step() {
+        let clock = ManualClock::new();
+        let target = Rate::new(50, Duration::from_secs(1));
+        let mut limiter = EmaLimiter::with_clock(clock.clone(), target, Duration::from_secs(1));
+        let emitted = Cell::new(0);
+        let mut per_second = Vec::new();
+        // 10 logs per second for 5s, then 1000 per second for 10s.
+        for (calls_per_second, seconds) in [(10, 5), (1000, 10)] {
+            for _ in 0..seconds {
+                emitted.set(0);
+                for _ in 0..calls_per_second {
+                    limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+                    clock.advance(Duration::from_secs(1) / calls_per_second);
+                }
+                per_second.push(emitted.get());
+            }
+        }
+
+        // Below the target everything passes.
+        assert!(per_second[..5].iter().all(|&emitted| emitted == 10));
+        // Once the estimate has caught up the output stays at the target.
+        assert!(
+            per_second[10..]
+                .iter()
+                .all(|emitted| (48..=52).contains(emitted)),
+            "{per_second:?}"
+        );
+        assert!((limiter.rate() - 50.0).abs() < 2.0, "{}", limiter.rate());
+    }
+}
@@ -0,0 +1,125 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+/// Logs with logarithmic backoff: only the 1st, `base`th, `base²`th, ... occurrence is emitted,
+/// e.g. the 1st, 2nd, 4th, 8th, ... for base 2. Sustained floods are reduced without any notion of
+/// time.
+pub struct ExponentialLimiter {
+    base: usize,
+    count: AtomicUsize,
+}
+
+impl ExponentialLimiter {
+    /// # Panics
+    /// If `base` is less than 2.
+    pub const fn new(base: usize) -> Self {
+        assert!(base >= 2, "the base must be at least 2");
+        Self {
+            base,
+            count: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of calls so far.
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    pub fn log_maybe(&self, log: impl Fn()) {
+        // Saturates rather than wrapping back to the start of the sequence.
+        let count = self
+            .count
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |count| {
+                Some(count.saturating_add(1))
+            })
+            .unwrap()
+            .saturating_add(1);
+        if is_power_of(count, self.base) {
+            log();
+        }
+    }
+}
+
+fn is_power_of(mut count: usize, base: usize) -> bool {
+    if count == 0 {
+        return false;
+    }
+    while count.is_multiple_of(base) {
+        count /= base;
+    }
+    count == 1
+}
+
+/// Logs at `$level` on the 1st, 2nd, 4th, 8th, ... call, or on powers of `base` if given. The
+/// count applies to the entire process.
+///
+/// ```
+/// log_limit::log_exponential!(log::Level::Warn, "Retrying...");
+/// log_limit::log_exponential!(base: 10, log::Level::Warn, "Retrying...");
+/// ```
+#[macro_export]
+macro_rules! log_exponential {
+    (base: $base:expr, $level:expr, $($arg:tt)+) => {{
+        static RATE_LIMITER: $crate::ExponentialLimiter = $crate::ExponentialLimiter::new($base);
+        // Logs filtered out by the logger mustn't advance the count.
+        if log::log_enabled!($level) {
+            RATE_LIMITER.log_maybe(|| log::log!($level, $($arg)+));
+        }
+    }};
+    ($level:expr, $($arg:tt)+) => {
+        $crate::log_exponential!(base: 2, $level, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExponentialLimiter;
+    use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::sync::atomic::AtomicUsize;
+
+    fn emissions(base: usize, calls: usize) -> Vec<usize> {
+        let limiter = ExponentialLimiter::new(base);
+        let emitted = RefCell::new(Vec::new());
+        for _ in 0..calls {
+            limiter.log_maybe(|| emitted.borrow_mut().push(limiter.count()));
+        }
+        emitted.into_inner()
+    }
+
+    #[test]
+    fn emits_on_powers_of_two() {
+        assert_eq!(emissions(2, 100), [1, 2, 4, 8, 16, 32, 64]);
+    }
+
+    #[test]
+    fn emits_on_powers_of_a_configured_base() {
+        assert_eq!(emissions(10, 1000), [1, 10, 100, 1000]);
+    }
+
+    #[test]
+    fn the_count_saturates_instead_of_wrapping() {
+        let limiter = ExponentialLimiter {
+            base: 2,
+            count: AtomicUsize::new(usize::MAX - 2),
+        };
+        let emitted = Cell::new(0);
+        for _ in 0..5 {
+            limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+        }
+        assert_eq!(limiter.count(), usize::MAX);
+        assert_eq!(emitted.get(), 0);
+        assert!(!super::is_power_of(0, 2));
+    }
+
+    #[test]
+    fn macro_emits_on_powers_of_two() {
+        crate::testing_logger::setup();
+        for _ in 0..100 {
+            log_exponential!(log::Level::Info, "Exponential");
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            assert_eq!(captured_logs.len(), 7);
+        });
+    }
+}
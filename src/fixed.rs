@@ -0,0 +1,111 @@
+use crate::Clock;
+use crate::Instant;
+use crate::SystemClock;
+use std::time::Duration;
+
+/// A fixed window limiter whose budget `MAX` is a const generic, for caps known at compile time.
+/// It doesn't store the budget, and the compiler can specialise the comparisons against it. The
+/// period remains a runtime [`Duration`].
+///
+/// A window starts with the first log and lasts `period`, after which the next log starts a new
+/// one.
+pub struct FixedLimiter<const MAX: usize, C: Clock = SystemClock> {
+    clock: C,
+    period: Duration,
+    window_start: Option<Instant>,
+    count: usize,
+    suppressed: usize,
+}
+
+impl<const MAX: usize> FixedLimiter<MAX> {
+    pub fn new(period: Duration) -> Self {
+        Self::with_clock(SystemClock, period)
+    }
+}
+
+impl<const MAX: usize, C: Clock> FixedLimiter<MAX, C> {
+    pub fn with_clock(clock: C, period: Duration) -> Self {
+        Self {
+            clock,
+            period,
+            window_start: None,
+            count: 0,
+            suppressed: 0,
+        }
+    }
+
+    /// Logs suppressed in the current window.
+    pub fn suppressed(&self) -> usize {
+        self.suppressed
+    }
+
+    #[inline]
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let now = self.clock.now();
+        let elapsed = self
+            .window_start
+            .map(|window_start| now.duration_since(window_start));
+        match elapsed {
+            Some(elapsed) if elapsed <= self.period => {}
+            _ => {
+                #[cfg(feature = "warning-messages")]
+                if let (Some(elapsed), true) = (elapsed, self.suppressed > 0) {
+                    crate::meta::warn_ignored(
+                        self.suppressed,
+                        elapsed,
+                        None,
+                        format_args!(
+                            "Ignored {} logs since {} ago. Starting to log again...",
+                            self.suppressed,
+                            crate::meta::FriendlyDuration(elapsed)
+                        ),
+                    );
+                }
+                self.window_start = Some(now);
+                self.count = 0;
+                self.suppressed = 0;
+            }
+        }
+
+        if self.count < MAX {
+            log();
+            self.count += 1;
+            #[cfg(feature = "warning-messages")]
+            if self.count == MAX {
+                crate::meta::warn_threshold(format_args!(
+                    "Hit logging threshold! Starting to ignore the previous log for {}",
+                    crate::meta::FriendlyDuration(self.period)
+                ));
+            }
+        } else {
+            self.suppressed += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FixedLimiter;
+    use crate::ManualClock;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn windows_hold_the_const_budget() {
+        let clock = ManualClock::new();
+        let period = Duration::from_secs(1);
+        let mut limiter = FixedLimiter::<10, _>::with_clock(clock.clone(), period);
+        let emitted = Cell::new(0);
+        let mut emissions = Vec::new();
+        for _ in 0..3 {
+            emitted.set(0);
+            for _ in 0..25 {
+                limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+            }
+            emissions.push((emitted.get(), limiter.suppressed()));
+            clock.advance(period * 3 / 5);
+        }
+        // The second batch still falls within the first window, the third starts a new one.
+        assert_eq!(emissions, [(10, 15), (0, 40), (10, 15)]);
+    }
+}
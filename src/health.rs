@@ -0,0 +1,94 @@
+use crate::Clock;
+use crate::RateLimiter;
+use crate::SystemClock;
+use std::time::Duration;
+
+/// The state of the process as far as [`HealthAwareLimiter`] is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Health {
+    Healthy,
+    /// E.g. the log backend is struggling, so it should be spared.
+    Degraded,
+}
+
+/// A fixed window limiter with one set of limits while healthy and another, typically stricter,
+/// while degraded. The state is read from `health` on every call, e.g. from a global flag, and
+/// both sets of limits draw from the same window, so degrading mid-window applies the stricter
+/// budget to what's already been logged.
+pub struct HealthAwareLimiter<F, C: Clock = SystemClock> {
+    rate_limiter: RateLimiter<C>,
+    health: F,
+    healthy: (usize, Duration),
+    degraded: (usize, Duration),
+}
+
+impl<F: Fn() -> Health> HealthAwareLimiter<F> {
+    /// Limits to `healthy` or `degraded`, each a `(max_per_time, period)` pair, depending on what
+    /// `health` returns.
+    pub fn new(health: F, healthy: (usize, Duration), degraded: (usize, Duration)) -> Self {
+        Self::with_clock(SystemClock, health, healthy, degraded)
+    }
+}
+
+impl<F: Fn() -> Health, C: Clock> HealthAwareLimiter<F, C> {
+    pub fn with_clock(
+        clock: C,
+        health: F,
+        healthy: (usize, Duration),
+        degraded: (usize, Duration),
+    ) -> Self {
+        Self {
+            rate_limiter: RateLimiter::with_clock(clock),
+            health,
+            healthy,
+            degraded,
+        }
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let (max_per_time, period) = match (self.health)() {
+            Health::Healthy => self.healthy,
+            Health::Degraded => self.degraded,
+        };
+        self.rate_limiter.log_maybe(period, max_per_time, log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Health;
+    use super::HealthAwareLimiter;
+    use crate::ManualClock;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn degrading_applies_the_stricter_limit() {
+        let clock = ManualClock::new();
+        let period = Duration::from_secs(1);
+        let health = Cell::new(Health::Healthy);
+        let mut limiter = HealthAwareLimiter::with_clock(
+            clock.clone(),
+            || health.get(),
+            (10, period),
+            (2, period),
+        );
+        let emitted = Cell::new(0);
+        let mut log_batch = |state, calls| {
+            health.set(state);
+            emitted.set(0);
+            for _ in 0..calls {
+                limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+            }
+            emitted.get()
+        };
+
+        assert_eq!(log_batch(Health::Healthy, 5), 5);
+        // Already over the degraded budget of the same window.
+        assert_eq!(log_batch(Health::Degraded, 5), 0);
+        clock.advance(period * 2);
+        assert_eq!(log_batch(Health::Degraded, 5), 2);
+        // Recovering within the window applies the looser budget to what's been logged.
+        assert_eq!(log_batch(Health::Healthy, 10), 8);
+    }
+}
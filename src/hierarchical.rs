@@ -0,0 +1,87 @@
+use crate::Clock;
+use crate::RateLimiter;
+use crate::SharedLimiter;
+use crate::SystemClock;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A child limiter with its own budget that also draws from the budget of a parent
+/// [`SharedLimiter`], so no child exceeds its own cap and all children of the parent together
+/// don't exceed the parent's cap. E.g. one child per subsystem under a parent for the whole
+/// service.
+///
+/// Logs within the child's budget are passed on to the parent, which may still suppress them.
+/// They count against the child's budget either way, so a child throttled by its parent doesn't
+/// burst once the parent's window rolls over.
+pub struct HierarchicalLimiter<C: Clock = SystemClock> {
+    parent: Arc<SharedLimiter<C>>,
+    rate_limiter: RateLimiter<C>,
+    max_per_time: usize,
+    period: Duration,
+}
+
+impl HierarchicalLimiter {
+    pub fn new(parent: Arc<SharedLimiter>, max_per_time: usize, period: Duration) -> Self {
+        Self::with_clock(SystemClock, parent, max_per_time, period)
+    }
+}
+
+impl<C: Clock> HierarchicalLimiter<C> {
+    pub fn with_clock(
+        clock: C,
+        parent: Arc<SharedLimiter<C>>,
+        max_per_time: usize,
+        period: Duration,
+    ) -> Self {
+        Self {
+            parent,
+            rate_limiter: RateLimiter::with_clock(clock),
+            max_per_time,
+            period,
+        }
+    }
+
+    pub fn parent(&self) -> &Arc<SharedLimiter<C>> {
+        &self.parent
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let parent = &self.parent;
+        self.rate_limiter
+            .log_maybe(self.period, self.max_per_time, || parent.log_maybe(&log));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HierarchicalLimiter;
+    use crate::ManualClock;
+    use crate::SharedLimiter;
+    use crate::SharedLimiterConfig;
+    use std::cell::Cell;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn children_together_are_capped_by_the_parent() {
+        let clock = ManualClock::new();
+        let period = Duration::from_secs(60);
+        let parent = Arc::new(SharedLimiter::with_clock(
+            clock.clone(),
+            SharedLimiterConfig::new(5, period),
+        ));
+        let mut children = [(); 2]
+            .map(|()| HierarchicalLimiter::with_clock(clock.clone(), parent.clone(), 3, period));
+        let emitted = Cell::new(0);
+        let mut per_child = Vec::new();
+        for child in &mut children {
+            emitted.set(0);
+            for _ in 0..10 {
+                child.log_maybe(|| emitted.set(emitted.get() + 1));
+            }
+            per_child.push(emitted.get());
+        }
+        // Each child is within its own cap of 3, but the second only gets what's left of the 5.
+        assert_eq!(per_child, [3, 2]);
+    }
+}
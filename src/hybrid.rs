@@ -0,0 +1,167 @@
+use crate::Clock;
+use crate::Instant;
+use crate::SystemClock;
+use std::time::Duration;
+
+/// A limiter allowing an initial burst followed by a steady trickle, e.g. 20 logs immediately and
+/// then one per second. The burst is a one-off allowance: once spent, logs are only let through
+/// one at a time at most once per `interval`, and idle time doesn't build up a new burst.
+pub struct HybridLimiter<C: Clock = SystemClock> {
+    clock: C,
+    burst_remaining: usize,
+    interval: Duration,
+    last_emitted_at: Option<Instant>,
+    #[cfg(feature = "warning-messages")]
+    suppressed: usize,
+}
+
+impl HybridLimiter {
+    pub fn new(burst: usize, interval: Duration) -> Self {
+        Self::with_clock(SystemClock, burst, interval)
+    }
+}
+
+impl<C: Clock> HybridLimiter<C> {
+    pub fn with_clock(clock: C, burst: usize, interval: Duration) -> Self {
+        Self {
+            clock,
+            burst_remaining: burst,
+            interval,
+            last_emitted_at: None,
+            #[cfg(feature = "warning-messages")]
+            suppressed: 0,
+        }
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let now = self.clock.now();
+
+        if self.burst_remaining > 0 {
+            log();
+            self.burst_remaining -= 1;
+            if self.burst_remaining == 0 {
+                self.last_emitted_at = Some(now);
+                #[cfg(feature = "warning-messages")]
+                crate::meta::warn_threshold(format_args!(
+                    "Hit burst threshold! Limiting the previous log to one every {}",
+                    crate::meta::FriendlyDuration(self.interval)
+                ));
+            }
+            return;
+        }
+
+        // Comparing the time since the last log rather than computing when the next one is due
+        // can't overflow, however long the interval.
+        match self.last_emitted_at {
+            Some(last_emitted_at) if now.duration_since(last_emitted_at) < self.interval => {
+                #[cfg(feature = "warning-messages")]
+                {
+                    self.suppressed += 1;
+                }
+            }
+            _ => {
+                #[cfg(feature = "warning-messages")]
+                if let (Some(last_emitted_at), true) = (self.last_emitted_at, self.suppressed > 0) {
+                    crate::meta::warn_ignored(
+                        self.suppressed,
+                        now.duration_since(last_emitted_at),
+                        None,
+                        format_args!(
+                            "Ignored {} logs since the last one. Logging one again...",
+                            self.suppressed
+                        ),
+                    );
+                    self.suppressed = 0;
+                }
+                log();
+                self.last_emitted_at = Some(now);
+            }
+        }
+    }
+}
+
+/// Logs at `$level` allowing an initial burst of `$burst` logs, then at most one log per
+/// `$interval`. The limit applies to a single thread.
+#[macro_export]
+macro_rules! hybrid_limit {
+    ($level:expr, $burst:expr, $interval:expr, $($arg:tt)+) => {{
+        use $crate::HybridLimiter;
+        use std::cell::RefCell;
+        use std::thread_local;
+
+        thread_local! {
+            static RATE_LIMITER: RefCell<HybridLimiter> = RefCell::new(HybridLimiter::new($burst, $crate::__period!($interval)));
+        }
+
+        RATE_LIMITER.with(|rate_limiter| {
+            // Dropped when logged reentrantly, as in `__limit!`.
+            if let Ok(mut rate_limiter) = rate_limiter.try_borrow_mut() {
+                rate_limiter.log_maybe(|| log::log!($level, $($arg)+))
+            }
+        });
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HybridLimiter;
+    use crate::ManualClock;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn burst_passes_then_settles_to_trickle() {
+        let clock = ManualClock::new();
+        let mut limiter = HybridLimiter::with_clock(clock.clone(), 20, Duration::from_secs(1));
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        for _ in 0..50 {
+            limiter.log_maybe(log);
+        }
+        assert_eq!(emitted.get(), 20);
+
+        // Ten calls per second for ten seconds only lets one through each second.
+        for _ in 0..10 {
+            clock.advance(Duration::from_secs(1));
+            for _ in 0..10 {
+                limiter.log_maybe(log);
+            }
+        }
+        assert_eq!(emitted.get(), 30);
+    }
+
+    #[test]
+    fn idle_time_does_not_build_a_new_burst() {
+        let clock = ManualClock::new();
+        let mut limiter = HybridLimiter::with_clock(clock.clone(), 2, Duration::from_secs(1));
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        limiter.log_maybe(log);
+        limiter.log_maybe(log);
+        clock.advance(Duration::from_secs(60));
+        for _ in 0..5 {
+            limiter.log_maybe(log);
+        }
+        assert_eq!(emitted.get(), 3);
+    }
+
+    #[test]
+    fn huge_interval_does_not_overflow() {
+        let clock = ManualClock::new();
+        let mut limiter = HybridLimiter::with_clock(clock.clone(), 1, Duration::MAX);
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        limiter.log_maybe(log);
+        clock.advance(Duration::from_secs(100 * 24 * 60 * 60));
+        limiter.log_maybe(log);
+        assert_eq!(emitted.get(), 1);
+    }
+
+    #[test]
+    fn macro_compiles() {
+        hybrid_limit!(log::Level::Info, 20, Duration::from_secs(1), "Hybrid {}", 1);
+    }
+}
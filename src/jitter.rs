@@ -0,0 +1,53 @@
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::time::Duration;
+
+/// Lengthens each window by a pseudo-random amount up to `max`, drawn from a splitmix64 sequence,
+/// so limiters started together don't all roll over, and summarise, at the same moment.
+pub(crate) struct Jitter {
+    max: Duration,
+    state: u64,
+    current: Duration,
+}
+
+impl Jitter {
+    pub(crate) fn new(max: Duration) -> Self {
+        Self::seeded(max, random_seed())
+    }
+
+    pub(crate) fn seeded(max: Duration, seed: u64) -> Self {
+        let mut jitter = Self {
+            max,
+            state: seed,
+            current: Duration::ZERO,
+        };
+        jitter.next_window();
+        jitter
+    }
+
+    /// The jitter of the current window.
+    pub(crate) fn current(&self) -> Duration {
+        self.current
+    }
+
+    /// Draws the jitter of the next window.
+    pub(crate) fn next_window(&mut self) {
+        let z = splitmix64(&mut self.state);
+        let max_nanos = u64::try_from(self.max.as_nanos()).unwrap_or(u64::MAX);
+        self.current = Duration::from_nanos(z % max_nanos.saturating_add(1));
+    }
+}
+
+/// A seed from the randomly keyed [`RandomState`], avoiding a dependency on a PRNG crate.
+pub(crate) fn random_seed() -> u64 {
+    RandomState::new().hash_one(0u8)
+}
+
+/// Advances `state` and returns the next number of its splitmix64 sequence.
+pub(crate) fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
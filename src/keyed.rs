@@ -0,0 +1,124 @@
+use crate::Clock;
+use crate::RateLimiter;
+use crate::SystemClock;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::time::Duration;
+
+struct Entry<C: Clock> {
+    rate_limiter: RateLimiter<C>,
+    last_used: u64,
+}
+
+/// A separate [`RateLimiter`] per key, e.g. per client IP.
+///
+/// At most `max_keys` keys are tracked. When a new key arrives while full, the least recently used
+/// key is evicted, which resets its budget: its next log is treated as if seen for the first time.
+/// Eviction scans all keys, so `max_keys` is best kept in the thousands rather than millions.
+pub struct KeyedRateLimiter<K, C: Clock + Clone = SystemClock> {
+    clock: C,
+    max_keys: usize,
+    limiters: HashMap<K, Entry<C>>,
+    uses: u64,
+}
+
+impl<K: Hash + Eq> KeyedRateLimiter<K> {
+    pub fn new(max_keys: usize) -> Self {
+        Self::with_clock(SystemClock, max_keys)
+    }
+}
+
+impl<K: Hash + Eq, C: Clock + Clone> KeyedRateLimiter<K, C> {
+    pub fn with_clock(clock: C, max_keys: usize) -> Self {
+        Self {
+            clock,
+            max_keys,
+            limiters: HashMap::new(),
+            uses: 0,
+        }
+    }
+
+    /// The number of keys currently tracked.
+    pub fn len(&self) -> usize {
+        self.limiters.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limiters.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.limiters.contains_key(key)
+    }
+
+    pub fn log_maybe(&mut self, key: K, period: Duration, max_per_time: usize, log: impl Fn()) {
+        self.uses += 1;
+        if !self.limiters.contains_key(&key) && self.limiters.len() >= self.max_keys {
+            self.evict_least_recently_used();
+        }
+        let clock = &self.clock;
+        let entry = self.limiters.entry(key).or_insert_with(|| Entry {
+            rate_limiter: RateLimiter::with_clock(clock.clone()),
+            last_used: 0,
+        });
+        entry.last_used = self.uses;
+        entry.rate_limiter.log_maybe(period, max_per_time, log);
+    }
+
+    fn evict_least_recently_used(&mut self) {
+        // Use counts are unique, so this removes exactly one key.
+        if let Some(oldest) = self.limiters.values().map(|entry| entry.last_used).min() {
+            self.limiters.retain(|_, entry| entry.last_used != oldest);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KeyedRateLimiter;
+    use crate::ManualClock;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    const PERIOD: Duration = Duration::from_secs(60);
+
+    #[test]
+    fn keys_have_independent_budgets() {
+        let mut limiter = KeyedRateLimiter::with_clock(ManualClock::new(), 10);
+        let emitted = Cell::new(0);
+        for key in ["10.0.0.1", "10.0.0.2"] {
+            for _ in 0..5 {
+                limiter.log_maybe(key, PERIOD, 2, || emitted.set(emitted.get() + 1));
+            }
+        }
+        assert_eq!(emitted.get(), 4);
+    }
+
+    #[test]
+    fn least_recently_used_key_is_evicted() {
+        let mut limiter = KeyedRateLimiter::with_clock(ManualClock::new(), 3);
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        for key in 0..3 {
+            limiter.log_maybe(key, PERIOD, 1, log);
+        }
+        // Key 0 is used again, leaving key 1 as the least recently used.
+        limiter.log_maybe(0, PERIOD, 1, log);
+        limiter.log_maybe(3, PERIOD, 1, log);
+        assert_eq!(limiter.len(), 3);
+        assert!(!limiter.contains_key(&1));
+        assert!(limiter.contains_key(&0));
+
+        for key in 4..10 {
+            limiter.log_maybe(key, PERIOD, 1, log);
+            assert_eq!(limiter.len(), 3);
+        }
+        assert!((7..10).all(|key| limiter.contains_key(&key)));
+
+        // An evicted key starts over with a fresh budget.
+        emitted.set(0);
+        limiter.log_maybe(1, PERIOD, 1, log);
+        assert_eq!(emitted.get(), 1);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::Clock;
+use crate::Instant;
+use crate::Rate;
+use crate::SystemClock;
+
+/// A leaky bucket holding up to `capacity` logs, which drains at a fixed `rate`. Each log adds
+/// one to the level of the bucket and is suppressed if it would overflow it.
+///
+/// Unlike a token bucket, which lets a burst through as long as tokens have built up, the bucket
+/// is full after a burst of `capacity` logs and only has room again as it drains, so sustained
+/// logging is let through at exactly `rate`.
+pub struct LeakyBucketLimiter<C: Clock = SystemClock> {
+    clock: C,
+    capacity: usize,
+    rate: Rate,
+    /// The level scaled so that each log adds `rate.per` in nanoseconds and each nanosecond drains
+    /// `rate.count`, keeping the draining exact.
+    level: u128,
+    drained_at: Option<Instant>,
+    /// When the current overflow started, for the summary.
+    #[cfg(feature = "warning-messages")]
+    overflowed_at: Option<Instant>,
+    suppressed: usize,
+}
+
+impl LeakyBucketLimiter {
+    pub fn new(capacity: usize, rate: Rate) -> Self {
+        Self::with_clock(SystemClock, capacity, rate)
+    }
+}
+
+impl<C: Clock> LeakyBucketLimiter<C> {
+    pub fn with_clock(clock: C, capacity: usize, rate: Rate) -> Self {
+        Self {
+            clock,
+            capacity,
+            rate,
+            level: 0,
+            drained_at: None,
+            #[cfg(feature = "warning-messages")]
+            overflowed_at: None,
+            suppressed: 0,
+        }
+    }
+
+    /// How many logs are in the bucket, rounded up.
+    pub fn level(&self) -> usize {
+        let level = self.drained_level(self.clock.now());
+        usize::try_from(level.div_ceil(self.rate.per.as_nanos())).unwrap_or(usize::MAX)
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        let now = self.clock.now();
+        self.level = self.drained_level(now);
+        self.drained_at = Some(now);
+
+        let log_size = self.rate.per.as_nanos();
+        let capacity = (self.capacity as u128).saturating_mul(log_size);
+        if self.level.saturating_add(log_size) <= capacity {
+            #[cfg(feature = "warning-messages")]
+            if let (Some(overflowed_at), true) = (self.overflowed_at, self.suppressed > 0) {
+                crate::meta::warn_ignored(
+                    self.suppressed,
+                    now.duration_since(overflowed_at),
+                    None,
+                    format_args!(
+                        "Ignored {} logs that overflowed the bucket. Logging again as it drains...",
+                        self.suppressed
+                    ),
+                );
+            }
+            self.suppressed = 0;
+            #[cfg(feature = "warning-messages")]
+            {
+                self.overflowed_at = None;
+            }
+            log();
+            self.level = self.level.saturating_add(log_size);
+        } else {
+            #[cfg(feature = "warning-messages")]
+            if self.suppressed == 0 {
+                self.overflowed_at = Some(now);
+                crate::meta::warn_threshold(format_args!(
+                    "Bucket full! Starting to ignore the previous log until it drains"
+                ));
+            }
+            self.suppressed += 1;
+        }
+    }
+
+    fn drained_level(&self, now: Instant) -> u128 {
+        let Some(drained_at) = self.drained_at else {
+            return self.level;
+        };
+        let drained = now
+            .duration_since(drained_at)
+            .as_nanos()
+            .saturating_mul(self.rate.count as u128);
+        self.level.saturating_sub(drained)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LeakyBucketLimiter;
+    use crate::ManualClock;
+    use crate::Rate;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn overflow_is_dropped_and_the_bucket_drains_steadily() {
+        let clock = ManualClock::new();
+        // Drains 2 logs per second, i.e. one every 500ms.
+        let rate = Rate::new(2, Duration::from_secs(1));
+        let mut limiter = LeakyBucketLimiter::with_clock(clock.clone(), 5, rate);
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        for _ in 0..20 {
+            limiter.log_maybe(log);
+        }
+        assert_eq!((emitted.get(), limiter.level()), (5, 5));
+
+        // Half a log drained isn't room for a whole one.
+        clock.advance(Duration::from_millis(250));
+        limiter.log_maybe(log);
+        assert_eq!(emitted.get(), 5);
+
+        // Flooding for ten seconds only lets through what drains.
+        clock.advance(Duration::from_millis(250));
+        for _ in 0..100 {
+            for _ in 0..10 {
+                limiter.log_maybe(log);
+            }
+            clock.advance(Duration::from_millis(100));
+        }
+        assert_eq!(emitted.get(), 5 + 1 + 19);
+
+        // Idling empties the bucket, without building up room beyond its capacity.
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(limiter.level(), 0);
+        for _ in 0..20 {
+            limiter.log_maybe(log);
+        }
+        assert_eq!((emitted.get(), limiter.level()), (30, 5));
+    }
+
+    #[test]
+    fn huge_rates_and_idle_times_saturate() {
+        let clock = ManualClock::new();
+        let rate = Rate::new(usize::MAX, Duration::MAX);
+        let mut limiter = LeakyBucketLimiter::with_clock(clock.clone(), usize::MAX, rate);
+        let emitted = Cell::new(0);
+        for _ in 0..3 {
+            limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+        }
+        assert_eq!(emitted.get(), 3);
+        // Drains far more than the level in nanoseconds times the rate can hold.
+        clock.advance(Duration::from_secs(u64::MAX / 8));
+        assert_eq!(limiter.level(), 0);
+        limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+        assert_eq!((emitted.get(), limiter.level()), (4, 1));
+    }
+}
@@ -1,19 +1,374 @@
 #![doc = include_str!("../README.md")]
 
+use log::Level;
+#[cfg(all(loom, test))]
+use loom::sync::atomic::AtomicUsize;
+#[cfg(all(loom, test))]
+use loom::sync::Mutex;
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::AtomicU64;
+#[cfg(not(all(loom, test)))]
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
 use std::sync::LazyLock;
+#[cfg(not(all(loom, test)))]
 use std::sync::Mutex;
+use std::sync::RwLock;
 use std::time::Duration;
-use std::time::Instant;
 
+mod adaptive;
+mod aligned;
+pub mod buffer;
+mod cardinality;
+pub mod category;
+mod clock;
+mod ema;
+mod exponential;
+mod fixed;
+mod health;
+mod hierarchical;
+mod hybrid;
+mod jitter;
+mod keyed;
+mod leaky;
+mod limiter;
+#[cfg(feature = "warning-messages")]
+mod meta;
+#[cfg(feature = "otel")]
+pub mod otel;
+#[cfg(feature = "overhead-timing")]
+mod overhead;
+pub mod policy;
+mod rate;
+#[cfg(feature = "registry")]
+pub mod registry;
+mod replay;
+mod sampling;
+mod shared;
+#[cfg(all(feature = "shared-memory", unix))]
+mod shared_memory;
+#[cfg(feature = "tracing")]
+mod span;
+mod spec;
+#[cfg(feature = "registry")]
+pub mod storm;
+mod streak;
+#[cfg(feature = "test-support")]
+pub mod test_support;
 #[cfg(test)]
 mod testing_logger;
+mod thread_sliced;
+mod tick;
+mod writer;
 
+pub use adaptive::AdaptivePeriodLimiter;
+pub use aligned::AlignedLimiter;
+pub use cardinality::CardinalityLimiter;
+pub use clock::Clock;
+pub use clock::Instant;
+pub use clock::ManualClock;
+pub use clock::SystemClock;
+pub use ema::EmaLimiter;
+pub use exponential::ExponentialLimiter;
+pub use fixed::FixedLimiter;
+pub use health::Health;
+pub use health::HealthAwareLimiter;
+pub use hierarchical::HierarchicalLimiter;
+pub use hybrid::HybridLimiter;
+pub use keyed::KeyedRateLimiter;
+pub use leaky::LeakyBucketLimiter;
+pub use limiter::AndLimiter;
+pub use limiter::FixedWindowLimiter;
+pub use limiter::Limiter;
+pub use limiter::OrLimiter;
+#[cfg(all(feature = "shared-memory", unix))]
+pub use limiter::SharedMemoryWindowLimiter;
+pub use limiter::SynchronisedWindowLimiter;
+pub use limiter::ThreadSlicedWindowLimiter;
+#[cfg(feature = "warning-messages")]
+pub use meta::clear_meta_formatter;
+#[cfg(feature = "warning-messages")]
+pub use meta::set_meta_ceiling;
+#[cfg(feature = "warning-messages")]
+pub use meta::set_meta_formatter;
+#[cfg(feature = "warning-messages")]
+pub use meta::MetaEvent;
+pub use rate::suggest_limits;
+pub use rate::Rate;
+pub use rate::Rounding;
+pub use replay::RecordedCall;
+pub use replay::Recording;
+pub use shared::BoostGuard;
+pub use shared::SharedLimiter;
+pub use shared::SharedLimiterConfig;
+#[cfg(all(feature = "shared-memory", unix))]
+pub use shared_memory::SharedMemoryLimiter;
+#[cfg(feature = "tracing")]
+pub use span::SpanLimiter;
+pub use spec::LimitSpec;
+pub use spec::ParseLimitSpecError;
+pub use streak::StreakLimiter;
+pub use thread_sliced::ThreadSlicedLimiter;
+pub use tick::TickLimiter;
+pub use writer::RateLimitedWriter;
+
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "registry")]
+    pub use linkme;
+
+    pub use crate::spec::into_period;
+    pub use crate::spec::parse_period;
+
+    /// A limiter with the config set by [`set_macro_config`](crate::set_macro_config).
+    pub fn macro_rate_limiter() -> crate::RateLimiter {
+        let config = *crate::MACRO_CONFIG.read().unwrap();
+        crate::RateLimiter::with_config(config.unwrap_or_default())
+    }
+
+    /// A global limiter with the [`approaching_percent`](crate::LimiterConfig::approaching_percent)
+    /// set by [`set_macro_config`](crate::set_macro_config).
+    pub fn macro_synchronised_rate_limiter() -> crate::SynchronisedRateLimiter {
+        let config = *crate::MACRO_CONFIG.read().unwrap();
+        let rate_limiter = crate::SynchronisedRateLimiter::with_clock(crate::SystemClock);
+        match config.and_then(|config| config.approaching_percent) {
+            Some(percent) => rate_limiter.with_approaching_percent(percent),
+            None => rate_limiter,
+        }
+    }
+    #[cfg(feature = "registry")]
+    pub use crate::storm::budget as storm_budget;
+    #[cfg(feature = "registry")]
+    pub use crate::storm::on_emit as storm_on_emit;
+
+    #[cfg(not(feature = "registry"))]
+    #[inline]
+    pub fn storm_budget(max_per_time: usize) -> usize {
+        max_per_time
+    }
+
+    #[cfg(not(feature = "registry"))]
+    #[inline]
+    pub fn storm_on_emit(_file: &'static str, _line: u32) {}
+
+    /// Keeps the log of the macros' call site at `file` and `line` if it was suppressed after
+    /// `suppressed` earlier ones, see [`LimiterConfig::emit_last_suppressed`](crate::LimiterConfig::emit_last_suppressed).
+    pub fn keep_suppressed(
+        rate_limiter: &mut crate::RateLimiter,
+        suppressed: usize,
+        context: &crate::LogContext,
+        location: (&'static str, u32),
+        message: impl FnOnce() -> String,
+    ) {
+        rate_limiter.keep_suppressed(suppressed, context, location, message);
+    }
+}
+
+#[cfg(not(feature = "registry"))]
 #[doc(hidden)]
-pub struct RateLimiter {
+#[macro_export]
+macro_rules! __register {
+    ($($tokens:tt)*) => {};
+}
+
+/// Behavioural options for a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LimiterConfig {
+    /// Use the very first call to a fresh limiter only to start the window, without emitting it.
+    /// Defaults to `false`, i.e. the first call is emitted immediately.
+    pub skip_first: bool,
+    /// Log over-budget messages at this level instead of dropping them, e.g. so they're still
+    /// recorded when tracing is enabled. They still count as suppressed. Only applies to
+    /// [`RateLimiter::log_maybe_with_context`], which the macros use. Defaults to `None`.
+    pub demote_to: Option<Level>,
+    /// Carry up to this much unused budget over into the next window, smoothing bursty but
+    /// infrequent logging. Windows then roll over on the first call after the period rather than
+    /// only once saturated, and budget left over from idle windows accumulates up to the cap.
+    /// This loosens the hard cap: an arbitrary period may contain up to `2 * max_per_time +
+    /// carryover_max` logs. Defaults to `0`, i.e. unused budget is lost.
+    pub carryover_max: usize,
+    /// Panic once more than this many logs are suppressed within a single window, naming the
+    /// call site. Meant for tests and CI, where a flood of logs usually means a bug such as an
+    /// infinite loop. Defaults to `None`, i.e. never panic.
+    pub panic_on_flood: Option<usize>,
+    /// Log a notice at this level once a full window passes within the budget after a flood,
+    /// e.g. an info "cleared" after throttled errors, distinguishing "still broken" from "fine
+    /// again". Needs the `warning-messages` feature. Defaults to `None`, i.e. no notice.
+    pub recovery_level: Option<Level>,
+    /// Log a one-time hint once a number of windows in a row each saw a single call, which means
+    /// the period is shorter than the time between calls and the limiter isn't limiting anything.
+    /// Needs the `warning-messages` feature. Defaults to `false`.
+    pub hint_misconfiguration: bool,
+    /// Emit over-budget logs anyway while still counting them as suppressed, including in the
+    /// metrics and warnings, e.g. to validate the limits before enforcing them. The summaries
+    /// then tell what would have been ignored. Defaults to `false`.
+    pub shadow: bool,
+    /// Log a notice once this percentage of a window's budget is used up, warning that logs are
+    /// about to be dropped before they are. [`set_macro_config`] applies it to the global macros'
+    /// and the [`policy`] limiters too, see [`SynchronisedRateLimiter::with_approaching_percent`].
+    /// Needs the `warning-messages` feature. Defaults to `None`, i.e. only the threshold itself is
+    /// logged.
+    pub approaching_percent: Option<u8>,
+    /// Log an escalated notice once this many windows in a row used up their budget, telling a
+    /// sustained flood apart from a transient burst. A full period without logs ends the streak.
+    /// Needs the `warning-messages` feature. Defaults to `None`, i.e. no escalation.
+    pub escalate_after: Option<u32>,
+    /// Keep the most recent suppressed log and emit it at its original level when the window
+    /// rolls over, right after the summary, so the latest state is visible even under heavy
+    /// suppression, with the file and line of its call site. It still counts as suppressed. Only
+    /// applies to the thread-local `*_limit!` macros, configured with [`set_macro_config`], and
+    /// to [`RateLimiter::log_message`], as only there the limiter has the message to keep, which
+    /// it then formats for every suppressed log. Defaults to `false`.
+    pub emit_last_suppressed: bool,
+    /// Apply [`LimiterConfig::grace_max_per_time`] instead of `max_per_time` for this many
+    /// windows after the first log, e.g. to capture a component's start-up in full before
+    /// settling to the real cap. Unlike a warm-up of fixed length, this counts windows, so it
+    /// scales with the period. Defaults to `0`, i.e. no grace.
+    pub grace_windows: usize,
+    /// The budget per window during [`LimiterConfig::grace_windows`]. Defaults to `None`, i.e.
+    /// unlimited.
+    pub grace_max_per_time: Option<usize>,
+}
+
+static MACRO_CONFIG: RwLock<Option<LimiterConfig>> = RwLock::new(None);
+
+/// Sets the config of the thread-local `*_limit!` macros' limiters, e.g. to enable
+/// [`LimiterConfig::hint_misconfiguration`] for them. Each call site's limiter takes the config
+/// set when it's first used on a thread, so set it early, before logging through the macros.
+/// The global macros' limiters and those of [`policy::set_limit`] only take its
+/// [`LimiterConfig::approaching_percent`], when first used and set respectively. Defaults to
+/// [`LimiterConfig::default`].
+pub fn set_macro_config(config: LimiterConfig) {
+    *MACRO_CONFIG.write().unwrap() = Some(config);
+}
+
+/// What the limiters know about the log being limited, letting them apply policies based on its
+/// level and target, see [`policy`]. The macros fill this in from their level and
+/// `module_path!()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LogContext<'a> {
+    pub level: Level,
+    pub target: &'a str,
+}
+
+/// The live accounting of a [`RateLimiter`], see [`RateLimiter::snapshot`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimiterSnapshot {
+    /// Logs emitted in the current window.
+    pub count: usize,
+    /// Logs suppressed in the current window.
+    pub suppressed: usize,
+    /// When the current window started.
+    pub window_start: Instant,
+}
+
+/// What a custom policy passed to [`RateLimiter::log_maybe_with`] gets to decide on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LimiterState {
+    /// Logs emitted in the current window.
+    pub count: usize,
+    /// Logs suppressed in the current window.
+    pub suppressed: usize,
+    /// How long ago the current window started.
+    pub elapsed: Duration,
+}
+
+/// What to do with a log, as returned by a custom policy passed to
+/// [`RateLimiter::log_maybe_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Decision {
+    Emit,
+    Suppress,
+    /// Emit the log as the first of a new window.
+    Rollover,
+}
+
+/// A hook called with the accounting of a window, see [`RateLimiter::on_window_start`].
+type WindowHook = dyn Fn(LimiterSnapshot) + Send + Sync;
+
+/// The rate limiter backing the thread-local `*_limit!` macros.
+///
+/// With the default [`LimiterConfig`] it guarantees, and the property tests check, that:
+/// - at most `2 * max_per_time` logs are emitted in any span of `period`, as such a span
+///   overlaps at most two windows of at most `max_per_time` logs each;
+/// - every call is either emitted or counted as suppressed, and the count reported when a window
+///   rolls over is exactly the number of calls suppressed in that window.
+///
+/// The `period` can be any [`Duration`], up to [`Duration::MAX`]. Windows are tracked by comparing
+/// the time elapsed since they started rather than by adding the period to an [`Instant`], so even
+/// huge periods can't overflow.
+///
+/// Neither this nor [`SynchronisedRateLimiter`] allocates when deciding whether to log, unless a
+/// custom formatter is set for the warnings.
+///
+/// A call within the budget still reads the clock and updates the counters. The warnings are
+/// `#[cold]` and kept out of line, as they're logged at most a few times per window, and without
+/// the `warning-messages` feature they aren't compiled at all, which `tests/no_meta_strings.rs`
+/// checks.
+///
+/// It is `Send` and `Sync` (given a `Send + Sync` clock), so it can be embedded in structs that
+/// cross threads, but logging needs `&mut self`. Sharing one between threads therefore needs a
+/// lock; use [`SynchronisedRateLimiter`] instead to avoid one on the fast path.
+///
+/// ```compile_fail
+/// use log_limit::RateLimiter;
+/// use std::time::Duration;
+///
+/// let rate_limiter = RateLimiter::new();
+/// let shared = &rate_limiter;
+/// shared.log_maybe(Duration::from_secs(1), 1, || {});
+/// ```
+pub struct RateLimiter<C: Clock = SystemClock> {
+    clock: C,
+    config: LimiterConfig,
     count: usize,
+    suppressed: usize,
+    carried_over: usize,
     timestamp: Instant,
+    created_at: Instant,
+    primed: bool,
+    /// Whether logs were suppressed since the last window that stayed within the budget.
+    flooded: bool,
+    /// The `period` and `max_per_time` of the last call over budget, for [`RateLimiter::poll`].
+    limits: Option<(Duration, usize)>,
+    /// Prefixed to the warnings, see [`RateLimiter::set_context`].
+    context: Option<String>,
+    /// The `period` of the last call, for [`RateLimiter::never_throttled`].
+    period: Option<Duration>,
+    /// Whether the budget was ever used up.
+    throttled: bool,
+    /// See [`RateLimiter::on_window_start`] and [`RateLimiter::on_window_end`].
+    on_window_start: Option<Box<WindowHook>>,
+    on_window_end: Option<Box<WindowHook>>,
+    /// Whether the window start hook has been called for the current window.
+    window_open: bool,
+    /// Windows in a row that saw a single call, see [`LimiterConfig::hint_misconfiguration`].
+    single_call_windows: u32,
+    /// When the previous call was made, for telling windows that saw a single call.
+    previous_call: Option<Instant>,
+    /// Windows in a row that used up their budget, see [`LimiterConfig::escalate_after`].
+    saturated_windows: u32,
+    /// See [`RateLimiter::with_jitter`].
+    jitter: Option<jitter::Jitter>,
+    /// When the window timer was frozen, see [`RateLimiter::pause`].
+    paused_at: Option<Instant>,
+    /// See [`LimiterConfig::emit_last_suppressed`].
+    last_suppressed: Option<SuppressedLog>,
+    /// See [`RateLimiter::on_decision_sampled`].
+    decision_sampler: Option<sampling::DecisionSampler>,
+    /// See [`RateLimiter::with_recording`], with the creation time the calls are relative to.
+    recording: Option<(Instant, Recording)>,
+    /// See [`RateLimiter::total_calls`].
+    total_calls: u64,
+    /// Windows ended so far, see [`LimiterConfig::grace_windows`].
+    ended_windows: usize,
+    /// See [`RateLimiter::subscribe`].
+    #[cfg(feature = "tokio")]
+    watch: Option<tokio::sync::watch::Sender<LimiterSnapshot>>,
+    #[cfg(feature = "overhead-timing")]
+    overhead_nanos: u64,
 }
 
 impl Default for RateLimiter {
@@ -24,423 +379,3497 @@ impl Default for RateLimiter {
 
 impl RateLimiter {
     pub fn new() -> Self {
+        Self::with_config(LimiterConfig::default())
+    }
+
+    pub fn with_config(config: LimiterConfig) -> Self {
+        Self::with_clock_and_config(SystemClock, config)
+    }
+}
+
+impl<C: Clock> RateLimiter<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self::with_clock_and_config(clock, LimiterConfig::default())
+    }
+
+    pub fn with_clock_and_config(clock: C, config: LimiterConfig) -> Self {
         Self {
             count: 0,
-            timestamp: Instant::now(),
+            suppressed: 0,
+            carried_over: 0,
+            timestamp: clock.now(),
+            created_at: clock.now(),
+            primed: !config.skip_first,
+            flooded: false,
+            limits: None,
+            context: None,
+            period: None,
+            throttled: false,
+            on_window_start: None,
+            on_window_end: None,
+            window_open: false,
+            single_call_windows: 0,
+            previous_call: None,
+            saturated_windows: 0,
+            jitter: None,
+            paused_at: None,
+            last_suppressed: None,
+            decision_sampler: None,
+            recording: None,
+            total_calls: 0,
+            ended_windows: 0,
+            #[cfg(feature = "tokio")]
+            watch: None,
+            #[cfg(feature = "overhead-timing")]
+            overhead_nanos: 0,
+            clock,
+            config,
         }
     }
 
-    pub fn log_maybe(&mut self, period: Duration, max_per_time: usize, log: impl Fn()) {
-        let now = Instant::now();
+    /// Lengthens each window by a random amount up to `jitter`, so limiters started together,
+    /// e.g. one per worker, spread out their rollovers and summaries instead of logging them all
+    /// at once. The randomness is seeded randomly, see [`RateLimiter::with_jitter_seeded`] to
+    /// make it reproducible.
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = Some(jitter::Jitter::new(jitter));
+        self
+    }
 
-        #[cfg(feature = "warning-messages")]
-        let calculated_duration = now.duration_since(self.timestamp);
-        if self.count < max_per_time {
-            log();
-            self.count += 1;
+    /// Like [`RateLimiter::with_jitter`], but draws the window lengths from `seed`, so the same
+    /// seed always gives the same sequence, e.g. in tests.
+    pub fn with_jitter_seeded(mut self, jitter: Duration, seed: u64) -> Self {
+        self.jitter = Some(jitter::Jitter::seeded(jitter, seed));
+        self
+    }
 
-            #[cfg(feature = "warning-messages")]
-            if self.count == max_per_time {
-                log::warn!(
-                    "Hit logging threshold! Starting to ignore the previous log for {:?}",
-                    calculated_duration
-                );
-            }
-        } else {
-            let calculated_duration = now.duration_since(self.timestamp);
-            if calculated_duration > period {
-                #[cfg(feature = "warning-messages")]
-                let filtered_log_count = self.count - max_per_time;
-                #[cfg(feature = "warning-messages")]
-                if filtered_log_count > 0 {
-                    log::warn!(
-                        "Ignored {filtered_log_count} logs since {:?} ago. Starting to log again...",
-                        calculated_duration
-                    );
-                }
-                log();
-                self.count = 1;
-                self.timestamp = now;
-            } else {
-                self.count += 1;
+    /// Calls `hook` with the accounting of each window as it opens, i.e. on its first log. The
+    /// first window counts as opening on the first log too.
+    pub fn on_window_start(&mut self, hook: impl Fn(LimiterSnapshot) + Send + Sync + 'static) {
+        self.on_window_start = Some(Box::new(hook));
+    }
+
+    /// Calls `hook` with the final accounting of each window as it rolls over, after the summary
+    /// of its suppressed logs is logged. Without
+    /// [`carryover_max`](LimiterConfig::carryover_max), windows only roll over once their budget
+    /// is used up, so these are the windows that had logs suppressed or were about to.
+    pub fn on_window_end(&mut self, hook: impl Fn(LimiterSnapshot) + Send + Sync + 'static) {
+        self.on_window_end = Some(Box::new(hook));
+    }
+
+    /// Subscribes to the final accounting of each window as it rolls over, e.g. to update a
+    /// dashboard live. The receiver starts out with the current accounting and only ever holds
+    /// the latest, see [`tokio::sync::watch`].
+    #[cfg(feature = "tokio")]
+    pub fn subscribe(&mut self) -> tokio::sync::watch::Receiver<LimiterSnapshot> {
+        match &self.watch {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = tokio::sync::watch::channel(self.snapshot());
+                self.watch = Some(sender);
+                receiver
             }
         }
     }
-}
 
-#[doc(hidden)]
-pub struct SynchronisedRateLimiter {
-    count: AtomicUsize,
-    timestamp: Mutex<Instant>,
-}
+    /// Calls `hook` with the time and outcome of a pseudo-random `rate` of the calls deciding
+    /// whether to log, e.g. 0.01 for 1%, to feed a profiler without instrumenting every call. The
+    /// first call of a limiter skipping it counts as suppressed. Unset, this costs a branch per
+    /// call. The calls are picked with a random seed, see
+    /// [`RateLimiter::on_decision_sampled_seeded`] to make it reproducible.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` isn't between 0 and 1.
+    pub fn on_decision_sampled(
+        &mut self,
+        rate: f64,
+        hook: impl Fn(Instant, Decision) + Send + Sync + 'static,
+    ) {
+        self.on_decision_sampled_seeded(rate, jitter::random_seed(), hook);
+    }
 
-impl SynchronisedRateLimiter {
-    pub const fn new() -> LazyLock<Self> {
-        LazyLock::new(|| Self {
-            count: AtomicUsize::new(0),
-            timestamp: Instant::now().into(),
-        })
+    /// Like [`RateLimiter::on_decision_sampled`], but picks the calls from `seed`, so the same
+    /// seed always samples the same calls, e.g. in tests.
+    ///
+    /// # Panics
+    ///
+    /// If `rate` isn't between 0 and 1.
+    pub fn on_decision_sampled_seeded(
+        &mut self,
+        rate: f64,
+        seed: u64,
+        hook: impl Fn(Instant, Decision) + Send + Sync + 'static,
+    ) {
+        self.decision_sampler = Some(sampling::DecisionSampler::new(rate, seed, Box::new(hook)));
     }
 
-    pub fn log_maybe(&self, period: Duration, max_per_time: usize, log: impl Fn()) {
-        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
-        if count <= max_per_time {
-            log();
-            #[cfg(feature = "warning-messages")]
-            if count == max_per_time {
-                log::warn!(
-                    "Hit logging threshold! Starting to ignore the previous log for more than {:?}",
-                    period
-                );
-            }
-        } else {
-            let now = Instant::now();
-            let mut timestamp = self.timestamp.lock().unwrap();
+    /// Records every call deciding whether to log, with its time, limits and outcome, so the
+    /// decisions can be reproduced offline with [`Recording::replay`], e.g. when debugging an
+    /// incident. Calls decided by a [`policy`] aren't recorded. The recording grows with every
+    /// call until taken with [`RateLimiter::take_recording`].
+    pub fn with_recording(mut self) -> Self {
+        self.recording = Some((self.created_at, Recording::new(self.config)));
+        self
+    }
 
-            let calculated_duration = now.duration_since(*timestamp);
-            if calculated_duration > period {
-                #[cfg(feature = "warning-messages")]
-                let filtered_log_count = self.count.swap(1, Ordering::Relaxed) - max_per_time - 1;
-                #[cfg(not(feature = "warning-messages"))]
-                let _filtered_log_count = self.count.swap(1, Ordering::Relaxed) - max_per_time - 1;
-                #[cfg(feature = "warning-messages")]
-                if filtered_log_count > 0 {
-                    log::warn!(
-                        "Ignored {filtered_log_count} logs since {:?} ago. Starting to log again...",
-                        calculated_duration
-                    );
-                }
-                log();
-                *timestamp = now;
-            }
-        }
+    /// Takes the calls recorded so far, continuing with an empty recording, or `None` if not
+    /// recording.
+    pub fn take_recording(&mut self) -> Option<Recording> {
+        let (_, recording) = self.recording.as_mut()?;
+        let config = recording.config;
+        Some(std::mem::replace(recording, Recording::new(config)))
     }
-}
 
-// TODO: Write a macro to dedup this
-#[macro_export]
-macro_rules! error_limit_global {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::SynchronisedRateLimiter;
-        use std::sync::LazyLock;
-        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
-        RATE_LIMITER.log_maybe($period, $max_per_time, || log::log!(log::Level::Error, $($arg)+));
-    }};
-}
+    /// Prefixes the threshold, ignored-count and recovery messages of this limiter with
+    /// `context`, e.g. the subsystem it's limiting, unless they're worded by a custom formatter or
+    /// the `json-meta` or `logfmt-meta` feature.
+    /// Unlike the options, it can change at any time, e.g. per request.
+    pub fn set_context(&mut self, context: String) {
+        self.context = Some(context);
+    }
 
-#[macro_export]
-macro_rules! warn_limit_global {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::SynchronisedRateLimiter;
-        use std::sync::LazyLock;
-        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
-        RATE_LIMITER.log_maybe($period, $max_per_time, || log::log!(log::Level::Warn, $($arg)+));
-    }};
-}
+    /// Replaces the options, keeping the accounting of the current window.
+    pub fn set_config(&mut self, config: LimiterConfig) {
+        self.config = config;
+    }
 
-#[macro_export]
-macro_rules! info_limit_global {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::SynchronisedRateLimiter;
-        use std::sync::LazyLock;
-        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
-        RATE_LIMITER.log_maybe($period, $max_per_time, || log::log!(log::Level::Info, $($arg)+));
-    }};
-}
+    /// Captures the live accounting, e.g. to carry it over into a reconfigured limiter.
+    pub fn snapshot(&self) -> LimiterSnapshot {
+        LimiterSnapshot {
+            count: self.count,
+            suppressed: self.suppressed,
+            window_start: self.timestamp,
+        }
+    }
 
-#[macro_export]
-macro_rules! debug_limit_global {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::SynchronisedRateLimiter;
-        use std::sync::LazyLock;
-        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
-        RATE_LIMITER.log_maybe($period, $max_per_time, || log::log!(log::Level::Debug, $($arg)+));
-    }};
-}
+    /// Logs the summary of the suppressed logs once the window is over, even if no further call
+    /// triggers a rollover, starting a new window. There's no background thread doing this:
+    /// call it periodically, e.g. from a timer or the main loop, when a call site may go quiet
+    /// after a flood.
+    pub fn poll(&mut self) {
+        let Some((period, max_per_time)) = self.limits else {
+            return;
+        };
+        let now = self.window_now();
+        let calculated_duration = now.duration_since(self.timestamp);
+        if self.suppressed == 0 || calculated_duration <= period {
+            return;
+        }
+        #[cfg(feature = "warning-messages")]
+        self.summarise_window(now, period, max_per_time);
+        #[cfg(not(feature = "warning-messages"))]
+        let _ = max_per_time;
+        self.roll_over_window(now);
+        self.carried_over = 0;
+    }
 
-#[macro_export]
-macro_rules! trace_limit_global {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::SynchronisedRateLimiter;
-        use std::sync::LazyLock;
-        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
-        RATE_LIMITER.log_maybe($period, $max_per_time, || log::log!(log::Level::Trace, $($arg)+));
-    }};
-}
+    /// Starts a fresh window right away, e.g. at the start of each request for a per-request
+    /// budget, logging the summary of the current one if it suppressed anything. Time based
+    /// rollovers carry on as before, so windows end at whichever comes first.
+    pub fn signal_reset(&mut self) {
+        let now = self.window_now();
+        #[cfg(feature = "warning-messages")]
+        if let (Some((period, max_per_time)), true) = (self.limits, self.suppressed > 0) {
+            self.summarise_window(now, period, max_per_time);
+        }
+        self.roll_over_window(now);
+        self.carried_over = 0;
+    }
 
-#[macro_export]
-macro_rules! error_limit {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::RateLimiter;
-        use std::cell::RefCell;
-        use std::thread_local;
+    /// Logs the summary of the suppressed logs of the window ending at `now`.
+    #[cfg(feature = "warning-messages")]
+    fn summarise_window(&self, now: Instant, period: Duration, max_per_time: usize) {
+        let calculated_duration = now.duration_since(self.timestamp);
+        meta::warn_ignored(
+            self.suppressed,
+            calculated_duration,
+            None,
+            meta::InContext(
+                self.context.as_deref(),
+                meta::Ignored {
+                    target: None,
+                    count: self.suppressed,
+                    since: calculated_duration,
+                    window_log_count: self.count + self.suppressed,
+                    max_per_time,
+                    period,
+                    shadow: self.config.shadow,
+                },
+            ),
+        );
+    }
 
-        thread_local! {
-            static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::new());
+    /// Starts afresh, as if just created but keeping its age.
+    #[cfg(feature = "test-support")]
+    pub(crate) fn reset(&mut self) {
+        self.count = 0;
+        self.suppressed = 0;
+        self.carried_over = 0;
+        self.paused_at = None;
+        self.timestamp = self.clock.now();
+        self.primed = !self.config.skip_first;
+        self.flooded = false;
+        self.limits = None;
+        self.period = None;
+        self.throttled = false;
+        self.window_open = false;
+        self.single_call_windows = 0;
+        self.previous_call = None;
+        self.saturated_windows = 0;
+        self.last_suppressed = None;
+        self.ended_windows = 0;
+    }
+
+    /// Freezes the window timer, e.g. before the process suspends, so the time until
+    /// [`RateLimiter::resume`] doesn't count toward the period: without it, a window outlasting
+    /// the suspension would end on resume and let a fresh burst through. Logs while paused are
+    /// limited against the frozen window.
+    pub fn pause(&mut self) {
+        if self.paused_at.is_none() {
+            self.paused_at = Some(self.clock.now());
         }
+    }
 
-        RATE_LIMITER.with(|rate_limiter| {
-            rate_limiter
-                .borrow_mut()
-                .log_maybe($period, $max_per_time, || log::log!(log::Level::Error, $($arg)+))
-        });
-    }};
-}
+    /// Unfreezes the window timer frozen by [`RateLimiter::pause`], moving the start of the
+    /// window forward by the time spent paused.
+    pub fn resume(&mut self) {
+        if let Some(paused_at) = self.paused_at.take() {
+            self.timestamp += self.clock.now().duration_since(paused_at);
+        }
+    }
 
-#[macro_export]
-macro_rules! warn_limit {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::RateLimiter;
-        use std::cell::RefCell;
-        use std::thread_local;
+    /// The time as far as the window is concerned, which stands still while paused.
+    fn window_now(&self) -> Instant {
+        self.paused_at.unwrap_or_else(|| self.clock.now())
+    }
 
-        thread_local! {
-            static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::new());
-        }
+    /// How long ago this limiter was created, which for the macros is when their call site was
+    /// first hit. Unlike the window, this is never reset, e.g. to spot call sites that have been
+    /// flapping for a long time.
+    pub fn age(&self) -> Duration {
+        self.clock.now().duration_since(self.created_at)
+    }
 
-        RATE_LIMITER.with(|rate_limiter| {
-            rate_limiter
-                .borrow_mut()
-                .log_maybe($period, $max_per_time, || log::log!(log::Level::Warn, $($arg)+))
-        });
-    }};
-}
+    /// Calls deciding whether to log so far, whatever the outcome, i.e. of `log_maybe` and its
+    /// variants other than [`RateLimiter::log_forced`]. Unlike the accounting of the window this
+    /// is never reset, e.g. for the overall call rate on a dashboard.
+    pub fn total_calls(&self) -> u64 {
+        self.total_calls
+    }
 
-#[macro_export]
-macro_rules! info_limit {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::RateLimiter;
-        use std::cell::RefCell;
-        use std::thread_local;
+    /// Whether this limiter has been used for at least a full window without ever using up its
+    /// budget, i.e. whether it's redundant at the current traffic and could be removed.
+    pub fn never_throttled(&self) -> bool {
+        !self.throttled && self.period.is_some_and(|period| self.age() >= period)
+    }
 
-        thread_local! {
-            static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::new());
+    /// How long until a log with these limits would be emitted, or `None` if it would be now, e.g.
+    /// to defer a log until it's let through. Once the window is saturated this is the time until
+    /// it ends, including any jitter, as the first log after it starts the next one.
+    pub fn next_allowed_in(&self, period: Duration, max_per_time: usize) -> Option<Duration> {
+        let period = match &self.jitter {
+            Some(jitter) => period.saturating_add(jitter.current()),
+            None => period,
+        };
+        let max_per_time = self.window_max(max_per_time);
+        if self.count < max_per_time.saturating_add(self.carried_over) {
+            return None;
         }
+        let elapsed = self.window_now().duration_since(self.timestamp);
+        // The window only rolls over once strictly past its period.
+        (elapsed <= period).then(|| (period - elapsed).max(Duration::from_nanos(1)))
+    }
 
-        RATE_LIMITER.with(|rate_limiter| {
-            rate_limiter
-                .borrow_mut()
-                .log_maybe($period, $max_per_time, || log::log!(log::Level::Info, $($arg)+))
-        });
-    }};
-}
+    /// Adds the accounting of `other` to this limiter's, e.g. when consolidating two call sites
+    /// into one, so the budget of the current window reflects both. The window starts at the
+    /// earlier of the two, and the age is that of the older limiter.
+    pub fn merge(&mut self, other: &RateLimiter<C>) {
+        self.count = self.count.saturating_add(other.count);
+        self.suppressed = self.suppressed.saturating_add(other.suppressed);
+        self.carried_over = self.carried_over.max(other.carried_over);
+        self.timestamp = self.timestamp.min(other.timestamp);
+        self.created_at = self.created_at.min(other.created_at);
+        self.total_calls = self.total_calls.saturating_add(other.total_calls);
+        self.primed |= other.primed;
+        self.flooded |= other.flooded;
+        self.throttled |= other.throttled;
+        self.limits = self.limits.or(other.limits);
+    }
 
-#[macro_export]
-macro_rules! debug_limit {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::RateLimiter;
-        use std::cell::RefCell;
-        use std::thread_local;
+    /// Continues the accounting captured by [`RateLimiter::snapshot`].
+    pub fn restore(&mut self, snapshot: LimiterSnapshot) {
+        self.count = snapshot.count;
+        self.suppressed = snapshot.suppressed;
+        self.timestamp = snapshot.window_start;
+        self.primed = true;
+    }
 
-        thread_local! {
-            static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::new());
+    #[track_caller]
+    pub fn log_maybe(&mut self, period: Duration, max_per_time: usize, log: impl Fn()) {
+        self.log_maybe_or(period, max_per_time, None, log, || {});
+    }
+
+    /// Like [`RateLimiter::log_maybe`], but takes the context of the log, applying any matching
+    /// [`policy`] instead of this limiter's budget. `log` is passed the level to log at so that
+    /// over-budget messages can be demoted, see [`LimiterConfig::demote_to`]. The summary of the
+    /// suppressed logs names the target of the call rolling the window over, which for the
+    /// macros' limiter per call site is that of the suppressed logs.
+    #[track_caller]
+    pub fn log_maybe_with_context(
+        &mut self,
+        period: Duration,
+        max_per_time: usize,
+        context: &LogContext,
+        log: impl Fn(Level),
+    ) {
+        if policy::log_maybe(context, || log(context.level)) {
+            self.total_calls = self.total_calls.saturating_add(1);
+            return;
         }
+        let demote_to = self.config.demote_to;
+        self.log_maybe_or(
+            period,
+            max_per_time,
+            Some(context.target),
+            || log(context.level),
+            || {
+                if let Some(demoted_level) = demote_to {
+                    log(demoted_level);
+                }
+            },
+        );
+    }
 
-        RATE_LIMITER.with(|rate_limiter| {
-            rate_limiter
-                .borrow_mut()
-                .log_maybe($period, $max_per_time, || log::log!(log::Level::Debug, $($arg)+))
+    /// Like [`RateLimiter::log_maybe_with_context`], but logs `message` itself, at the context's
+    /// level to its target and with the file and line of the caller, so it can keep the most
+    /// recent suppressed one, see [`LimiterConfig::emit_last_suppressed`].
+    #[track_caller]
+    pub fn log_message(
+        &mut self,
+        period: Duration,
+        max_per_time: usize,
+        context: &LogContext,
+        message: fmt::Arguments,
+    ) {
+        let caller = std::panic::Location::caller();
+        let location = (caller.file(), caller.line());
+        let suppressed = self.suppressed;
+        self.log_maybe_with_context(period, max_per_time, context, |level| {
+            log_at(level, context.target, location, message)
         });
-    }};
-}
+        self.keep_suppressed(suppressed, context, location, || message.to_string());
+    }
 
-#[macro_export]
-macro_rules! trace_limit {
-    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
-        use $crate::RateLimiter;
-        use std::cell::RefCell;
-        use std::thread_local;
+    /// Keeps the log at `location` if it was suppressed after `suppressed` earlier ones, see
+    /// [`LimiterConfig::emit_last_suppressed`].
+    fn keep_suppressed(
+        &mut self,
+        suppressed: usize,
+        context: &LogContext,
+        location: (&'static str, u32),
+        message: impl FnOnce() -> String,
+    ) {
+        // Shadowed logs were emitted anyway, so there's nothing to make up for.
+        if self.config.emit_last_suppressed && !self.config.shadow && self.suppressed > suppressed {
+            self.last_suppressed = Some(SuppressedLog {
+                level: context.level,
+                target: context.target.to_owned(),
+                location,
+                message: message(),
+            });
+        }
+    }
 
-        thread_local! {
-            static RATE_LIMITER: RefCell<RateLimiter> = RefCell::new(RateLimiter::new());
+    /// Like [`RateLimiter::log_maybe`], but leaves the decision to `policy`, which inspects the
+    /// state of the current window. The built-in fixed window is one such policy:
+    ///
+    /// ```
+    /// use log_limit::Decision;
+    /// use log_limit::RateLimiter;
+    /// use std::time::Duration;
+    ///
+    /// let (max_per_time, period) = (10, Duration::from_secs(1));
+    /// let mut rate_limiter = RateLimiter::new();
+    /// rate_limiter.log_maybe_with(
+    ///     |state| {
+    ///         if state.count < max_per_time {
+    ///             Decision::Emit
+    ///         } else if state.elapsed > period {
+    ///             Decision::Rollover
+    ///         } else {
+    ///             Decision::Suppress
+    ///         }
+    ///     },
+    ///     || log::info!("Hello"),
+    /// );
+    /// ```
+    pub fn log_maybe_with(&mut self, policy: impl Fn(&LimiterState) -> Decision, log: impl Fn()) {
+        self.total_calls = self.total_calls.saturating_add(1);
+        let now = self.window_now();
+
+        if !self.primed {
+            self.primed = true;
+            self.timestamp = now;
+            return;
         }
 
-        RATE_LIMITER.with(|rate_limiter| {
-            rate_limiter
-                .borrow_mut()
-                .log_maybe($period, $max_per_time, || log::log!(log::Level::Trace, $($arg)+))
-        });
-    }};
-}
+        let elapsed = now.duration_since(self.timestamp);
+        let state = LimiterState {
+            count: self.count,
+            suppressed: self.suppressed,
+            elapsed,
+        };
+        let decision = policy(&state);
+        if let Some(sampler) = &mut self.decision_sampler {
+            if sampler.sample() {
+                sampler.record(self.clock.now(), decision);
+            }
+        }
+        match decision {
+            Decision::Emit => {
+                if !self.window_open {
+                    self.open_window();
+                }
+                log();
+                self.count += 1;
+            }
+            Decision::Suppress => {
+                self.suppressed += 1;
+                self.throttled = true;
+            }
+            Decision::Rollover => {
+                #[cfg(feature = "warning-messages")]
+                if self.suppressed > 0 {
+                    meta::warn_ignored(
+                        self.suppressed,
+                        elapsed,
+                        None,
+                        meta::InContext(
+                            self.context.as_deref(),
+                            format_args!(
+                                "Ignored {} logs since {} ago. Starting to log again...",
+                                self.suppressed,
+                                meta::FriendlyDuration(elapsed)
+                            ),
+                        ),
+                    );
+                }
+                self.roll_over_window(now);
+                self.open_window();
+                log();
+                self.count = 1;
+            }
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::info_limit_global;
-    use std::thread;
-    use std::time::Duration;
-    use std::time::Instant;
+    /// Logs regardless of the budget, for the one occurrence that mustn't be lost. It still
+    /// counts toward the budget of the current window.
+    pub fn log_forced(&mut self, log: impl Fn()) {
+        #[cfg(feature = "otel")]
+        otel::record_emitted();
+        log();
+        self.count = self.count.saturating_add(1);
+    }
 
-    enum LoggerVariant {
-        TheadLocal,
-        Shared,
+    /// Nanoseconds spent deciding whether to log so far, excluding the logging itself.
+    #[cfg(feature = "overhead-timing")]
+    pub fn overhead_nanos(&self) -> u64 {
+        self.overhead_nanos
     }
 
-    fn logger_limits_correctly(variant_under_test: LoggerVariant) {
-        crate::testing_logger::setup();
+    #[track_caller]
+    fn log_maybe_or(
+        &mut self,
+        period: Duration,
+        max_per_time: usize,
+        target: Option<&str>,
+        log: impl Fn(),
+        suppress: impl Fn(),
+    ) {
+        let sampled = self
+            .decision_sampler
+            .as_mut()
+            .is_some_and(|sampler| sampler.sample());
+        // Recorded with the time the call was decided at, so replaying it decides the same.
+        let now = self.window_now();
+
+        #[cfg(feature = "overhead-timing")]
+        let timer = overhead::Timer::start();
+        #[cfg(feature = "overhead-timing")]
+        let (log, suppress) = (|| timer.exclude(&log), || timer.exclude(&suppress));
+
+        let decision = self.log_maybe_untimed(now, period, max_per_time, target, log, suppress);
+
+        #[cfg(feature = "overhead-timing")]
+        {
+            self.overhead_nanos = self.overhead_nanos.saturating_add(timer.elapsed_nanos());
+        }
+
+        if sampled {
+            if let Some(sampler) = &self.decision_sampler {
+                sampler.record(now, decision);
+            }
+        }
+        if let Some((start, recording)) = &mut self.recording {
+            recording.calls.push(RecordedCall {
+                at: now.duration_since(*start),
+                period,
+                max_per_time,
+                decision,
+            });
+        }
+    }
+
+    /// Decides whether to log, returning the outcome. A call starting a new window is a
+    /// [`Decision::Rollover`] whichever way the window ended.
+    #[track_caller]
+    fn log_maybe_untimed(
+        &mut self,
+        now: Instant,
+        period: Duration,
+        max_per_time: usize,
+        target: Option<&str>,
+        log: impl Fn(),
+        suppress: impl Fn(),
+    ) -> Decision {
+        #[cfg(not(feature = "warning-messages"))]
+        let _ = target;
+        self.total_calls = self.total_calls.saturating_add(1);
+        let shadow = self.config.shadow;
+        let suppress = || {
+            if shadow {
+                log();
+            } else {
+                suppress();
+            }
+        };
+        #[cfg(feature = "otel")]
+        let (log, suppress) = (
+            || {
+                otel::record_emitted();
+                log();
+            },
+            || {
+                otel::record_suppressed();
+                suppress();
+            },
+        );
+        self.period = Some(period);
+        if self.config.hint_misconfiguration {
+            self.track_single_call_windows(now, period);
+        }
+        let period = match &self.jitter {
+            Some(jitter) => period.saturating_add(jitter.current()),
+            None => period,
+        };
+
+        if !self.primed {
+            self.primed = true;
+            self.timestamp = now;
+            return Decision::Suppress;
+        }
+
+        if self.flooded
+            && self.count
+                < self
+                    .window_max(max_per_time)
+                    .saturating_add(self.carried_over)
+            && now.duration_since(self.timestamp) > period
+        {
+            self.flooded = false;
+            #[cfg(feature = "warning-messages")]
+            if let Some(level) = self.config.recovery_level {
+                meta::notify_recovered(
+                    level,
+                    meta::InContext(
+                        self.context.as_deref(),
+                        format_args!("Flood subsided, the previous log is within its limit again"),
+                    ),
+                );
+            }
+        }
+
+        // Grace windows may never saturate, so they end with the first log after their period.
+        let mut rolled_over = false;
+        if self.ended_windows < self.config.grace_windows
+            && self.suppressed == 0
+            && now.duration_since(self.timestamp) > period
+        {
+            self.roll_over_window(now);
+            rolled_over = true;
+        }
+        let max_per_time = self.window_max(max_per_time);
+
+        if self.config.carryover_max > 0 {
+            rolled_over |= self.carry_over_unused_budget(now, period, max_per_time);
+        }
+        let budget = max_per_time.saturating_add(self.carried_over);
+
+        #[cfg(feature = "warning-messages")]
+        let calculated_duration = now.duration_since(self.timestamp);
+        if self.count < budget {
+            if !self.window_open {
+                self.open_window();
+            }
+            log();
+            self.count += 1;
+            self.throttled |= self.count == budget;
+
+            #[cfg(feature = "warning-messages")]
+            if let Some(percent) = self.config.approaching_percent {
+                self.warn_approaching(percent, budget);
+            }
+            #[cfg(feature = "warning-messages")]
+            if self.count == budget {
+                meta::warn_threshold(meta::InContext(
+                    self.context.as_deref(),
+                    format_args!(
+                        "Hit logging threshold! Starting to ignore the previous log for {}",
+                        meta::FriendlyDuration(calculated_duration)
+                    ),
+                ));
+            }
+            if rolled_over {
+                Decision::Rollover
+            } else {
+                Decision::Emit
+            }
+        } else {
+            self.limits = Some((period, max_per_time));
+            self.throttled = true;
+            let calculated_duration = now.duration_since(self.timestamp);
+            if calculated_duration > period {
+                #[cfg(feature = "warning-messages")]
+                if self.suppressed > 0 {
+                    meta::warn_ignored(
+                        self.suppressed,
+                        calculated_duration,
+                        target,
+                        meta::InContext(
+                            self.context.as_deref(),
+                            meta::Ignored {
+                                target,
+                                count: self.suppressed,
+                                since: calculated_duration,
+                                window_log_count: self.count + self.suppressed,
+                                max_per_time,
+                                period,
+                                shadow: self.config.shadow,
+                            },
+                        ),
+                    );
+                }
+                self.roll_over_window(now);
+                self.open_window();
+                log();
+                self.count = 1;
+                self.carried_over = 0;
+                #[cfg(feature = "warning-messages")]
+                if let Some(percent) = self.config.approaching_percent {
+                    self.warn_approaching(percent, max_per_time);
+                }
+                Decision::Rollover
+            } else {
+                suppress();
+                self.suppressed += 1;
+                self.flooded = true;
+                if self
+                    .config
+                    .panic_on_flood
+                    .is_some_and(|threshold| self.suppressed > threshold)
+                {
+                    panic!(
+                        "Log flood at {}: suppressed {} logs within {:?}, exceeding the panic_on_flood threshold",
+                        std::panic::Location::caller(),
+                        self.suppressed,
+                        calculated_duration
+                    );
+                }
+                Decision::Suppress
+            }
+        }
+    }
+
+    /// Starts a new window if the current unsaturated one has expired, carrying over what's left
+    /// of its budget and that of any idle windows since. Returns whether it did.
+    fn carry_over_unused_budget(
+        &mut self,
+        now: Instant,
+        period: Duration,
+        max_per_time: usize,
+    ) -> bool {
+        let budget = max_per_time.saturating_add(self.carried_over);
+        let elapsed = now.duration_since(self.timestamp);
+        if self.count >= budget || elapsed <= period {
+            return false;
+        }
+        let idle_windows = elapsed.as_nanos() / period.as_nanos().max(1) - 1;
+        let idle_budget = usize::try_from(idle_windows)
+            .unwrap_or(usize::MAX)
+            .saturating_mul(max_per_time);
+        self.carried_over = (budget - self.count)
+            .saturating_add(idle_budget)
+            .min(self.config.carryover_max);
+        self.roll_over_window(now);
+        true
+    }
+
+    /// Ends the current window, starting a new one at `now` that opens on its first log.
+    fn roll_over_window(&mut self, now: Instant) {
+        if let (Some(hook), true) = (&self.on_window_end, self.window_open) {
+            hook(self.snapshot());
+        }
+        #[cfg(feature = "tokio")]
+        if let Some(sender) = &self.watch {
+            sender.send_replace(self.snapshot());
+        }
+        if let Some(escalate_after) = self.config.escalate_after {
+            self.track_saturated_windows(now, escalate_after);
+        }
+        if let Some(last_suppressed) = self.last_suppressed.take() {
+            log_at(
+                last_suppressed.level,
+                &last_suppressed.target,
+                last_suppressed.location,
+                format_args!("{}", last_suppressed.message),
+            );
+        }
+        self.count = 0;
+        self.suppressed = 0;
+        self.timestamp = now;
+        self.window_open = false;
+        self.ended_windows = self.ended_windows.saturating_add(1);
+        if let Some(jitter) = &mut self.jitter {
+            jitter.next_window();
+        }
+    }
+
+    fn track_saturated_windows(&mut self, now: Instant, escalate_after: u32) {
+        if self.suppressed == 0 {
+            self.saturated_windows = 0;
+            return;
+        }
+        self.saturated_windows = self.saturated_windows.saturating_add(1);
+        #[cfg(feature = "warning-messages")]
+        if self.saturated_windows == escalate_after {
+            meta::warn_sustained(
+                escalate_after,
+                meta::InContext(
+                    self.context.as_deref(),
+                    format_args!(
+                        "Sustained flooding! The previous log used up its budget in {escalate_after} windows in a row"
+                    ),
+                ),
+            );
+        }
+        #[cfg(not(feature = "warning-messages"))]
+        let _ = escalate_after;
+        // The window rolled over late, so the period after it was quiet.
+        let elapsed = now.duration_since(self.timestamp);
+        if self.period.is_some_and(|period| elapsed > period * 2) {
+            self.saturated_windows = 0;
+        }
+    }
+
+    /// The budget of the current window, raised during [`LimiterConfig::grace_windows`].
+    fn window_max(&self, max_per_time: usize) -> usize {
+        if self.ended_windows < self.config.grace_windows {
+            self.config.grace_max_per_time.unwrap_or(usize::MAX)
+        } else {
+            max_per_time
+        }
+    }
+
+    /// Hints once that the period is too short after this many single call windows in a row.
+    const SINGLE_CALL_WINDOWS_HINT: u32 = 8;
+
+    /// Counts a window that saw a single call if this call comes more than `period` after the
+    /// previous one. Windows only roll over once saturated, so this can't wait for a rollover.
+    fn track_single_call_windows(&mut self, now: Instant, period: Duration) {
+        let Some(previous_call) = self.previous_call.replace(now) else {
+            return;
+        };
+        if now.duration_since(previous_call) <= period {
+            // Stops counting once hinted, so the hint is only logged once.
+            if self.single_call_windows < Self::SINGLE_CALL_WINDOWS_HINT {
+                self.single_call_windows = 0;
+            }
+            return;
+        }
+        self.single_call_windows = self.single_call_windows.saturating_add(1);
+        #[cfg(feature = "warning-messages")]
+        if self.single_call_windows == Self::SINGLE_CALL_WINDOWS_HINT {
+            let period = self.period.unwrap_or_default();
+            meta::hint_misconfigured(meta::InContext(
+                self.context.as_deref(),
+                format_args!(
+                    "The previous log's last {} windows each saw a single call, so it isn't being limited. Its period of {} is likely shorter than the time between calls; consider a longer one",
+                    Self::SINGLE_CALL_WINDOWS_HINT,
+                    meta::FriendlyDuration(period)
+                ),
+            ));
+        }
+    }
+
+    /// Warns once the count reaches `percent` of `budget`, unless that's nothing or all of it.
+    #[cfg(feature = "warning-messages")]
+    fn warn_approaching(&self, percent: u8, budget: usize) {
+        if is_approaching(self.count, percent, budget) {
+            meta::warn_approaching(
+                percent,
+                meta::InContext(
+                    self.context.as_deref(),
+                    format_args!(
+                        "Used up {percent}% of the logging budget, {} more logs until the previous log is ignored",
+                        budget - self.count
+                    ),
+                ),
+            );
+        }
+    }
+
+    #[cold]
+    fn open_window(&mut self) {
+        self.window_open = true;
+        if let Some(hook) = &self.on_window_start {
+            hook(self.snapshot());
+        }
+    }
+}
+
+/// Whether `count` is the `percent` of `budget` to warn at, unless that's nothing or all of it.
+#[cfg(feature = "warning-messages")]
+fn is_approaching(count: usize, percent: u8, budget: usize) -> bool {
+    let approaching = budget.saturating_mul(usize::from(percent)) / 100;
+    count == approaching && approaching > 0 && approaching < budget
+}
+
+/// A suppressed log kept for [`LimiterConfig::emit_last_suppressed`].
+struct SuppressedLog {
+    level: Level,
+    target: String,
+    /// The file and line of its call site.
+    location: (&'static str, u32),
+    message: String,
+}
+
+/// Logs `message` as if from `location` rather than from this crate, which `log::log!` would
+/// record.
+fn log_at(level: Level, target: &str, location: (&'static str, u32), message: fmt::Arguments) {
+    if level <= log::max_level() {
+        log::logger().log(
+            &log::Record::builder()
+                .level(level)
+                .target(target)
+                .file_static(Some(location.0))
+                .line(Some(location.1))
+                .args(message)
+                .build(),
+        );
+    }
+}
+
+/// The ordering of the counters of [`SynchronisedRateLimiter`].
+///
+/// `Relaxed` by default: each call's increment is a single atomic read-modify-write, so no call
+/// is lost or double counted and the budget is exact regardless. Suppressions are only counted,
+/// and windows only rolled over, under the lock that [`SynchronisedRateLimiter::snapshot`] takes
+/// too, so a snapshot never mixes the counts of two windows nor sees a suppression before its
+/// call under either ordering. The `strict-ordering` feature uses `SeqCst` instead, putting every
+/// change of the counters in a single order all threads agree on, at the cost of a fence per call
+/// on weakly ordered hardware such as ARM. None of the limiter's guarantees depend on it. The
+/// `cfg(loom)` model tests check them, run with `RUSTFLAGS="--cfg loom" cargo test --lib loom`,
+/// with or without the feature.
+#[cfg(not(feature = "strict-ordering"))]
+const COUNTER_ORDERING: Ordering = Ordering::Relaxed;
+#[cfg(feature = "strict-ordering")]
+const COUNTER_ORDERING: Ordering = Ordering::SeqCst;
+
+/// The count of calls a [`SynchronisedRateLimiter`] window saturates at, so budgets are exact up
+/// to this many calls per window. Far enough below `usize::MAX` that the increments racing with
+/// the clamp can't wrap the count around to within the budget again.
+const MAX_COUNT: usize = usize::MAX / 2;
+
+/// Increments `counter`, clamping it at [`MAX_COUNT`], and returns the incremented count. A plain
+/// check after the increment keeps a compare-exchange loop off the hot path.
+#[inline]
+fn increment_saturating(counter: &AtomicUsize) -> usize {
+    let count = counter.fetch_add(1, COUNTER_ORDERING) + 1;
+    if count > MAX_COUNT {
+        counter.store(MAX_COUNT, COUNTER_ORDERING);
+    }
+    count.min(MAX_COUNT)
+}
+
+/// The rate limiter backing the `*_limit_global!` macros. It is `Send` and `Sync` (given a
+/// `Send + Sync` clock) and logs through `&self`, so it can be shared freely between threads.
+///
+/// It is also cancellation safe: `log_maybe` never awaits, so a future using it, e.g. in a
+/// `select!` branch, can only be dropped between calls and never leaves a call half applied.
+/// The one lock it takes is never held while calling `log`, so a panicking `log` leaves the
+/// limiter usable.
+///
+/// The count of calls saturates at `usize::MAX / 2` per window rather than wrapping, so even an
+/// endless flood can't wrap it around to within the budget. Budgets are exact up to that count.
+#[doc(hidden)]
+pub struct SynchronisedRateLimiter<C: Clock = SystemClock> {
+    clock: C,
+    count: AtomicUsize,
+    suppressed: AtomicUsize,
+    timestamp: Mutex<Instant>,
+    created_at: Instant,
+    /// The `period` and `max_per_time` of the last call over budget, for
+    /// [`SynchronisedRateLimiter::poll`]. Only locked while holding `timestamp`.
+    limits: Mutex<Option<(Duration, usize)>>,
+    /// The `period` in nanoseconds as of the first call of the window, for
+    /// [`SynchronisedRateLimiter::never_throttled`].
+    period_nanos: AtomicU64,
+    /// Whether the budget was ever used up.
+    throttled: AtomicBool,
+    /// See [`SynchronisedRateLimiter::total_calls`].
+    total_calls: AtomicU64,
+    /// See [`SynchronisedRateLimiter::with_approaching_percent`].
+    approaching_percent: Option<u8>,
+    #[cfg(feature = "overhead-timing")]
+    overhead_nanos: AtomicU64,
+}
+
+impl SynchronisedRateLimiter {
+    pub const fn new() -> LazyLock<Self> {
+        LazyLock::new(|| Self::with_clock(SystemClock))
+    }
+}
+
+impl<C: Clock> SynchronisedRateLimiter<C> {
+    pub fn with_clock(clock: C) -> Self {
+        Self {
+            count: AtomicUsize::new(0),
+            suppressed: AtomicUsize::new(0),
+            timestamp: Mutex::new(clock.now()),
+            created_at: clock.now(),
+            limits: Mutex::new(None),
+            // Until known, a period so long it's never exceeded.
+            period_nanos: AtomicU64::new(u64::MAX),
+            throttled: AtomicBool::new(false),
+            total_calls: AtomicU64::new(0),
+            approaching_percent: None,
+            #[cfg(feature = "overhead-timing")]
+            overhead_nanos: AtomicU64::new(0),
+            clock,
+        }
+    }
+
+    /// Logs a notice once `percent` of a window's budget is used up, see
+    /// [`LimiterConfig::approaching_percent`].
+    pub fn with_approaching_percent(mut self, percent: u8) -> Self {
+        self.approaching_percent = Some(percent);
+        self
+    }
+
+    /// Warns once the `count` reaches the configured percentage of `max_per_time`.
+    #[cfg(feature = "warning-messages")]
+    fn warn_approaching(&self, count: usize, max_per_time: usize) {
+        let Some(percent) = self.approaching_percent else {
+            return;
+        };
+        if is_approaching(count, percent, max_per_time) {
+            meta::warn_approaching(
+                percent,
+                format_args!(
+                    "Used up {percent}% of the logging budget, {} more logs until the previous log is ignored",
+                    max_per_time - count
+                ),
+            );
+        }
+    }
+
+    /// Captures the accounting of the current window. Calls racing with the snapshot may or may
+    /// not be included, but it never mixes the counts of two windows.
+    pub fn snapshot(&self) -> LimiterSnapshot {
+        // Windows only roll over under the lock.
+        let window_start = self.timestamp.lock().unwrap();
+        let suppressed = self.suppressed.load(COUNTER_ORDERING);
+        LimiterSnapshot {
+            count: self.count.load(COUNTER_ORDERING).saturating_sub(suppressed),
+            suppressed,
+            window_start: *window_start,
+        }
+    }
+
+    /// Logs the summary of the suppressed logs once the window is over, even if no further call
+    /// triggers a rollover, see [`RateLimiter::poll`].
+    pub fn poll(&self) {
+        let mut timestamp = self.timestamp.lock().unwrap();
+        let Some((period, max_per_time)) = *self.limits.lock().unwrap() else {
+            return;
+        };
+        let now = self.clock.now();
+        let calculated_duration = now.duration_since(*timestamp);
+        if calculated_duration <= period || self.count.load(COUNTER_ORDERING) <= max_per_time {
+            return;
+        }
+        let window_log_count = self.count.swap(0, COUNTER_ORDERING);
+        self.suppressed.store(0, COUNTER_ORDERING);
+        *timestamp = now;
+        drop(timestamp);
+        #[cfg(not(feature = "warning-messages"))]
+        let _ = window_log_count;
+        #[cfg(feature = "warning-messages")]
+        {
+            let filtered_log_count = window_log_count.saturating_sub(max_per_time);
+            meta::warn_ignored(
+                filtered_log_count,
+                calculated_duration,
+                None,
+                meta::Ignored {
+                    target: None,
+                    count: filtered_log_count,
+                    since: calculated_duration,
+                    window_log_count,
+                    max_per_time,
+                    period,
+                    shadow: false,
+                },
+            );
+        }
+    }
+
+    /// Starts afresh, as if just created but keeping its age.
+    #[cfg(feature = "test-support")]
+    pub(crate) fn reset(&self) {
+        let mut timestamp = self.timestamp.lock().unwrap();
+        self.count.store(0, COUNTER_ORDERING);
+        self.suppressed.store(0, COUNTER_ORDERING);
+        *self.limits.lock().unwrap() = None;
+        self.period_nanos.store(u64::MAX, Ordering::Relaxed);
+        self.throttled.store(false, Ordering::Relaxed);
+        *timestamp = self.clock.now();
+    }
+
+    /// Sets the count of calls in the current window, to test counts no test could reach.
+    #[cfg(test)]
+    fn set_count(&self, count: usize) {
+        self.count.store(count, COUNTER_ORDERING);
+    }
+
+    /// How long ago this limiter was created, see [`RateLimiter::age`].
+    pub fn age(&self) -> Duration {
+        self.clock.now().duration_since(self.created_at)
+    }
+
+    /// Calls deciding whether to log so far, across all threads, see [`RateLimiter::total_calls`].
+    pub fn total_calls(&self) -> u64 {
+        self.total_calls.load(Ordering::Relaxed)
+    }
+
+    /// Whether this limiter has been used for at least a full window without ever using up its
+    /// budget, see [`RateLimiter::never_throttled`].
+    pub fn never_throttled(&self) -> bool {
+        let period = Duration::from_nanos(self.period_nanos.load(Ordering::Relaxed));
+        !self.throttled.load(Ordering::Relaxed) && self.age() >= period
+    }
+
+    /// Like [`SynchronisedRateLimiter::log_maybe`], but takes the context of the log, applying
+    /// any matching [`policy`] instead of this limiter's budget.
+    pub fn log_maybe_with_context(
+        &self,
+        period: Duration,
+        max_per_time: usize,
+        context: &LogContext,
+        log: impl Fn(),
+    ) {
+        if policy::log_maybe(context, &log) {
+            self.total_calls.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.log_maybe_targeted(period, max_per_time, Some(context.target), log);
+        }
+    }
+
+    /// Logs regardless of the budget, for the one occurrence that mustn't be lost. It still
+    /// counts toward the budget of the current window.
+    pub fn log_forced(&self, log: impl Fn()) {
+        #[cfg(feature = "otel")]
+        otel::record_emitted();
+        log();
+        increment_saturating(&self.count);
+    }
+
+    /// Nanoseconds spent deciding whether to log so far, across all threads and excluding the
+    /// logging itself.
+    #[cfg(feature = "overhead-timing")]
+    pub fn overhead_nanos(&self) -> u64 {
+        self.overhead_nanos.load(Ordering::Relaxed)
+    }
+
+    pub fn log_maybe(&self, period: Duration, max_per_time: usize, log: impl Fn()) {
+        self.log_maybe_targeted(period, max_per_time, None, log);
+    }
+
+    fn log_maybe_targeted(
+        &self,
+        period: Duration,
+        max_per_time: usize,
+        target: Option<&str>,
+        log: impl Fn(),
+    ) {
+        #[cfg(feature = "overhead-timing")]
+        let timer = overhead::Timer::start();
+        #[cfg(feature = "overhead-timing")]
+        let log = || timer.exclude(&log);
+
+        self.log_maybe_untimed(period, max_per_time, target, log);
+
+        #[cfg(feature = "overhead-timing")]
+        self.overhead_nanos
+            .fetch_add(timer.elapsed_nanos(), Ordering::Relaxed);
+    }
+
+    fn log_maybe_untimed(
+        &self,
+        period: Duration,
+        max_per_time: usize,
+        target: Option<&str>,
+        log: impl Fn(),
+    ) {
+        #[cfg(not(feature = "warning-messages"))]
+        let _ = target;
+        self.total_calls.fetch_add(1, Ordering::Relaxed);
+        #[cfg(feature = "otel")]
+        let log = || {
+            otel::record_emitted();
+            log();
+        };
+        let count = increment_saturating(&self.count);
+        // Only once per window, as a store on every call would contend.
+        if count == 1 {
+            let period_nanos = u64::try_from(period.as_nanos()).unwrap_or(u64::MAX);
+            self.period_nanos.store(period_nanos, Ordering::Relaxed);
+        }
+        if count <= max_per_time {
+            log();
+            if count == max_per_time {
+                self.throttled.store(true, Ordering::Relaxed);
+            }
+            #[cfg(feature = "warning-messages")]
+            self.warn_approaching(count, max_per_time);
+            #[cfg(feature = "warning-messages")]
+            if count == max_per_time {
+                meta::warn_threshold(format_args!(
+                    "Hit logging threshold! Starting to ignore the previous log for more than {}",
+                    meta::FriendlyDuration(period)
+                ));
+            }
+        } else {
+            let now = self.clock.now();
+            let mut timestamp = self.timestamp.lock().unwrap();
+            // Only once per window, keeping the lock off the hot path.
+            if count - 1 == max_per_time {
+                *self.limits.lock().unwrap() = Some((period, max_per_time));
+                self.throttled.store(true, Ordering::Relaxed);
+            }
+
+            let calculated_duration = now.duration_since(*timestamp);
+            if calculated_duration > period {
+                let window_log_count = self.count.swap(1, COUNTER_ORDERING) - 1;
+                self.suppressed.store(0, COUNTER_ORDERING);
+                *timestamp = now;
+                // Neither the summary nor `log` may run under the lock, as a panic in them would
+                // poison it.
+                drop(timestamp);
+                #[cfg(not(feature = "warning-messages"))]
+                let _ = window_log_count;
+                #[cfg(feature = "warning-messages")]
+                let filtered_log_count = window_log_count - max_per_time;
+                #[cfg(feature = "warning-messages")]
+                if filtered_log_count > 0 {
+                    meta::warn_ignored(
+                        filtered_log_count,
+                        calculated_duration,
+                        target,
+                        meta::Ignored {
+                            target,
+                            count: filtered_log_count,
+                            since: calculated_duration,
+                            window_log_count,
+                            max_per_time,
+                            period,
+                            shadow: false,
+                        },
+                    );
+                }
+                log();
+                #[cfg(feature = "warning-messages")]
+                self.warn_approaching(1, max_per_time);
+            } else {
+                #[cfg(feature = "otel")]
+                otel::record_suppressed();
+                increment_saturating(&self.suppressed);
+            }
+        }
+    }
+}
+
+// Checked at compile time so that changing a field can't silently drop these guarantees.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<RateLimiter>();
+    assert_send_sync::<RateLimiter<ManualClock>>();
+    assert_send_sync::<SynchronisedRateLimiter>();
+    assert_send_sync::<SynchronisedRateLimiter<ManualClock>>();
+    assert_send_sync::<HybridLimiter>();
+    assert_send_sync::<FixedWindowLimiter>();
+    assert_send_sync::<AndLimiter<FixedWindowLimiter, HybridLimiter>>();
+    assert_send_sync::<OrLimiter<FixedWindowLimiter, HybridLimiter>>();
+    assert_send_sync::<SharedLimiter>();
+    assert_send_sync::<HierarchicalLimiter>();
+    assert_send_sync::<ThreadSlicedLimiter>();
+    assert_send_sync::<TickLimiter>();
+    assert_send_sync::<AlignedLimiter>();
+    assert_send_sync::<ExponentialLimiter>();
+    assert_send_sync::<StreakLimiter>();
+    assert_send_sync::<FixedLimiter<10>>();
+    assert_send_sync::<LeakyBucketLimiter>();
+    assert_send_sync::<EmaLimiter>();
+    assert_send_sync::<AdaptivePeriodLimiter>();
+    assert_send_sync::<CardinalityLimiter>();
+    assert_send_sync::<HealthAwareLimiter<fn() -> Health>>();
+    #[cfg(all(feature = "shared-memory", unix))]
+    assert_send_sync::<SharedMemoryLimiter>();
+    #[cfg(feature = "tracing")]
+    assert_send_sync::<SpanLimiter>();
+    assert_send_sync::<ManualClock>();
+};
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __limit_global {
+    ($level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: false; $level, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {{
+        use $crate::SynchronisedRateLimiter;
+        use std::sync::LazyLock;
+        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> =
+            LazyLock::new($crate::__private::macro_synchronised_rate_limiter);
+        $crate::__register!($level, $crate::registry::LimiterKind::Global(&RATE_LIMITER));
+        // Logs filtered out by the logger mustn't use up the budget.
+        if log::log_enabled!($level) {
+            let context = $crate::LogContext {
+                level: $level,
+                target: module_path!(),
+            };
+            let emitted = std::cell::Cell::new(false);
+            let log = || {
+                emitted.set(true);
+                $crate::__private::storm_on_emit(file!(), line!());
+                $crate::buffer::on_emit($level);
+                log::log!($level, $($arg)+)
+            };
+            if $force {
+                RATE_LIMITER.log_forced(log);
+            } else {
+                RATE_LIMITER.log_maybe_with_context(
+                    $crate::__period!($period),
+                    $crate::__private::storm_budget($max_per_time),
+                    &context,
+                    log,
+                );
+            }
+            if !emitted.get() {
+                $crate::buffer::on_suppress(&context, || std::format!($($arg)+));
+            }
+        }
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __limit {
+    ($level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: false; $level, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {{
+        let _ = $crate::__limit!(counted: false, force: $force; $level, $max_per_time, $period, $($arg)+);
+    }};
+    // Evaluates to the number of logs suppressed in the current window if `$counted`, or 0.
+    (counted: $counted:expr, force: $force:expr; $level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {{
+        use $crate::RateLimiter;
+        use std::cell::RefCell;
+        use std::thread_local;
+
+        thread_local! {
+            static RATE_LIMITER: RefCell<RateLimiter> =
+                RefCell::new($crate::__private::macro_rate_limiter());
+        }
+        $crate::__register!($level, $crate::registry::LimiterKind::ThreadLocal(&RATE_LIMITER));
+
+        // Logs filtered out by the logger mustn't use up the budget.
+        if log::log_enabled!($level) {
+            let context = $crate::LogContext {
+                level: $level,
+                target: module_path!(),
+            };
+            let emitted = std::cell::Cell::new(false);
+            let log = |level| {
+                emitted.set(true);
+                $crate::__private::storm_on_emit(file!(), line!());
+                $crate::buffer::on_emit(level);
+                log::log!(level, $($arg)+)
+            };
+            RATE_LIMITER.with(|rate_limiter| {
+                // A logger logging through this call site again from within `log` finds the
+                // limiter borrowed, and the nested log is dropped rather than panicking.
+                let Ok(mut rate_limiter) = rate_limiter.try_borrow_mut() else {
+                    return;
+                };
+                if $force {
+                    rate_limiter.log_forced(|| log($level));
+                } else {
+                    let suppressed = rate_limiter.snapshot().suppressed;
+                    rate_limiter.log_maybe_with_context(
+                        $crate::__period!($period),
+                        $crate::__private::storm_budget($max_per_time),
+                        &context,
+                        log,
+                    );
+                    $crate::__private::keep_suppressed(
+                        &mut rate_limiter,
+                        suppressed,
+                        &context,
+                        (file!(), line!()),
+                        || std::format!($($arg)+),
+                    );
+                }
+            });
+            if !emitted.get() {
+                $crate::buffer::on_suppress(&context, || std::format!($($arg)+));
+            }
+        }
+        if $counted {
+            RATE_LIMITER.with(|rate_limiter| {
+                rate_limiter
+                    .try_borrow()
+                    .map_or(0, |rate_limiter| rate_limiter.snapshot().suppressed)
+            })
+        } else {
+            0
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! error_limit_global {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: true; log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: $force; log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn_limit_global {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: true; log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: $force; log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info_limit_global {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: true; log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: $force; log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug_limit_global {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: true; log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: $force; log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace_limit_global {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: true; log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(force: $force; log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit_global!(log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! error_limit {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: true; log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: $force; log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn_limit {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: true; log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: $force; log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info_limit {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: true; log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: $force; log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug_limit {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: true; log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: $force; log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace_limit {
+    (force; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: true; log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+    (force: $force:expr; $max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(force: $force; log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+}
+
+/// Like the `*_limit!` macros, but evaluates to the number of logs suppressed at this call site
+/// in the current window, e.g. to react once too many have been dropped.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// let dropped = log_limit::info_limit_counted!(10, Duration::from_secs(1), "Hello");
+/// if dropped > 100 {
+///     // raise_alarm();
+/// }
+/// ```
+#[macro_export]
+macro_rules! error_limit_counted {
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(counted: true, force: false; log::Level::Error, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! warn_limit_counted {
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(counted: true, force: false; log::Level::Warn, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! info_limit_counted {
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(counted: true, force: false; log::Level::Info, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! debug_limit_counted {
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(counted: true, force: false; log::Level::Debug, $max_per_time, $period, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! trace_limit_counted {
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {
+        $crate::__limit!(counted: true, force: false; log::Level::Trace, $max_per_time, $period, $($arg)+)
+    };
+}
+
+/// Evaluates to whether this call site is within its budget of `$max_per_time` per `$period`,
+/// for gating arbitrary code rather than a single log. The limit applies to the entire process.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// if log_limit::should_log!(10, Duration::from_secs(1)) {
+///     // do_expensive_logging();
+/// }
+/// ```
+#[macro_export]
+macro_rules! should_log {
+    ($max_per_time:expr, $period:expr) => {{
+        use std::cell::Cell;
+        use std::sync::LazyLock;
+        use $crate::SynchronisedRateLimiter;
+        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
+        let allowed = Cell::new(false);
+        RATE_LIMITER.log_maybe($crate::__period!($period), $max_per_time, || {
+            allowed.set(true)
+        });
+        allowed.get()
+    }};
+}
+
+/// Limits every unqualified `error!`, `warn!`, `info!`, `debug!` and `trace!` call in `$body`
+/// to a single shared budget of `$max_per_time` per `$period`, instead of one per call site. The
+/// limit applies to a single thread. Calls by path, such as `log::info!`, aren't limited.
+///
+/// ```
+/// use log::info;
+/// use log::warn;
+/// use std::time::Duration;
+///
+/// log_limit::limit_scope!(10, Duration::from_secs(1); {
+///     info!("Connecting");
+///     warn!("Retrying");
+/// });
+/// ```
+#[macro_export]
+macro_rules! limit_scope {
+    ($max_per_time:expr, $period:expr; $body:block) => {
+        $crate::__limit_scope!(($) $max_per_time, $period; $body)
+    };
+}
+
+// Takes a `$` token to write the nested macros' repetitions with.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __limit_scope {
+    (($d:tt) $max_per_time:expr, $period:expr; $body:block) => {{
+        use std::cell::RefCell;
+        use std::thread_local;
+        use $crate::RateLimiter;
+
+        thread_local! {
+            static SCOPE_RATE_LIMITER: RefCell<RateLimiter> =
+                RefCell::new($crate::__private::macro_rate_limiter());
+        }
+
+        #[allow(unused_macros)]
+        macro_rules! __scoped_log {
+            ($level:expr, $d($arg:tt)+) => {
+                if log::log_enabled!($level) {
+                    let context = $crate::LogContext {
+                        level: $level,
+                        target: module_path!(),
+                    };
+                    SCOPE_RATE_LIMITER.with(|rate_limiter| {
+                        // Dropped when logged reentrantly, as in `__limit!`.
+                        if let Ok(mut rate_limiter) = rate_limiter.try_borrow_mut() {
+                            rate_limiter.log_maybe_with_context(
+                                $crate::__period!($period),
+                                $max_per_time,
+                                &context,
+                                |level| log::log!(level, $d($arg)+),
+                            )
+                        }
+                    });
+                }
+            };
+        }
+        #[allow(unused_macros)]
+        macro_rules! error {
+            ($d($arg:tt)+) => {
+                __scoped_log!(log::Level::Error, $d($arg)+)
+            };
+        }
+        #[allow(unused_macros)]
+        macro_rules! warn {
+            ($d($arg:tt)+) => {
+                __scoped_log!(log::Level::Warn, $d($arg)+)
+            };
+        }
+        #[allow(unused_macros)]
+        macro_rules! info {
+            ($d($arg:tt)+) => {
+                __scoped_log!(log::Level::Info, $d($arg)+)
+            };
+        }
+        #[allow(unused_macros)]
+        macro_rules! debug {
+            ($d($arg:tt)+) => {
+                __scoped_log!(log::Level::Debug, $d($arg)+)
+            };
+        }
+        #[allow(unused_macros)]
+        macro_rules! trace {
+            ($d($arg:tt)+) => {
+                __scoped_log!(log::Level::Trace, $d($arg)+)
+            };
+        }
+
+        $body
+    }};
+}
+
+/// Prints to stderr like `eprintln!`, but at most `$max_per_time` times per `$period`, for
+/// diagnostics in environments where `log` isn't set up. The limit applies to the entire process.
+#[macro_export]
+macro_rules! eprint_limit {
+    ($max_per_time:expr, $period:expr, $($arg:tt)+) => {{
+        use std::sync::LazyLock;
+        use $crate::SynchronisedRateLimiter;
+        static RATE_LIMITER: LazyLock<SynchronisedRateLimiter> = SynchronisedRateLimiter::new();
+        RATE_LIMITER.log_maybe(
+            $crate::__period!($period),
+            $max_per_time,
+            || std::eprintln!($($arg)+),
+        );
+    }};
+}
+
+/// Logs at `$level`, rate limited like the `*_limit!` macros in release builds but emitting every
+/// log in builds with `debug_assertions`, so floods are only suppressed in production.
+#[macro_export]
+macro_rules! release_limit {
+    ($level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {{
+        #[cfg(not(debug_assertions))]
+        $crate::__limit!($level, $max_per_time, $period, $($arg)+);
+        #[cfg(debug_assertions)]
+        log::log!($level, $($arg)+);
+    }};
+}
+
+/// Logs at `$level`, rate limited like the `*_limit!` macros but with the limits of `$category`
+/// as listed by [`category::set_table`], resolved on every call.
+///
+/// ```
+/// log_limit::category_limit!("auth", log::Level::Warn, "Token expired for {}", "alice");
+/// ```
+#[macro_export]
+macro_rules! category_limit {
+    ($category:expr, $level:expr, $($arg:tt)+) => {{
+        let spec = $crate::category::resolve($category);
+        $crate::__limit!($level, spec.max_per_time, spec.period, $($arg)+)
+    }};
+}
+
+/// Logs `$message`, any `Display` value, at `$level`, rate limited like the `*_limit!` macros,
+/// for messages that are expensive to build. `$message` is only evaluated once
+/// `log::log_enabled!($level)` holds and the call site is within its budget, so with the level
+/// filtered out neither the message is built nor the budget used up. With
+/// [`buffer::set_capacity`] it's also built for the suppressed logs the buffer keeps.
+///
+/// ```
+/// use std::time::Duration;
+///
+/// # fn dump_connection_pool() -> String { String::new() }
+/// log_limit::lazy_limit!(
+///     log::Level::Debug,
+///     10,
+///     Duration::from_secs(1),
+///     dump_connection_pool()
+/// );
+/// ```
+#[macro_export]
+macro_rules! lazy_limit {
+    ($level:expr, $max_per_time:expr, $period:expr, $message:expr $(,)?) => {
+        $crate::__limit!($level, $max_per_time, $period, "{}", $message)
+    };
+}
+
+/// Logs at `$level` with the budget of `$rate_limiter`, a [`RateLimiter`] of your own, instead of
+/// one per call site. The `*_limit!` macros keep their limiter in a `static`, so every closure
+/// created from the same source, e.g. by a factory, shares a single budget; capture a fresh
+/// limiter in each closure to give each a budget of its own.
+///
+/// ```
+/// use log_limit::RateLimiter;
+/// use std::time::Duration;
+///
+/// fn handler(name: &'static str) -> impl FnMut() {
+///     let mut rate_limiter = RateLimiter::new();
+///     move || {
+///         log_limit::per_instance_limit!(
+///             rate_limiter,
+///             log::Level::Warn,
+///             10,
+///             Duration::from_secs(1),
+///             "{name} failed"
+///         )
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! per_instance_limit {
+    ($rate_limiter:expr, $level:expr, $max_per_time:expr, $period:expr, $($arg:tt)+) => {{
+        // Logs filtered out by the logger mustn't use up the budget.
+        if log::log_enabled!($level) {
+            let context = $crate::LogContext {
+                level: $level,
+                target: module_path!(),
+            };
+            let period = $crate::__period!($period);
+            $rate_limiter.log_maybe_with_context(period, $max_per_time, &context, |level| {
+                log::log!(level, $($arg)+)
+            });
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Clock;
+    use super::Decision;
+    use super::LimiterConfig;
+    use super::LimiterSnapshot;
+    use super::LimiterState;
+    use super::LogContext;
+    use super::ManualClock;
+    use super::RateLimiter;
+    use super::SynchronisedRateLimiter;
+    use proptest::prelude::*;
+    use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use std::thread;
+    use std::time::Duration;
+    use std::time::Instant;
+
+    enum LoggerVariant {
+        TheadLocal,
+        Shared,
+    }
+
+    fn logger_limits_correctly(variant_under_test: LoggerVariant) {
+        crate::testing_logger::setup();
         for _ in 0..11 {
             match variant_under_test {
                 LoggerVariant::TheadLocal => {
                     info_limit!(2, Duration::from_millis(50), "Logging on repeat")
                 }
-                LoggerVariant::Shared => {
-                    info_limit_global!(2, Duration::from_millis(50), "Logging on repeat")
+                LoggerVariant::Shared => {
+                    info_limit_global!(2, Duration::from_millis(50), "Logging on repeat")
+                }
+            }
+            thread::sleep(Duration::from_millis(11));
+            // 00: Log
+            // 11: Log (and warn of omission)
+            // 22: Omit
+            // 33: Omit
+            // 44: Omit
+            // 55: Log (and warn: missed 3)
+            // 66: Log (and warn of omission)
+            // 77: Omit
+            // 88: Omit
+            // 99: Omit
+            // 110: Log (and warn: missed 3)
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            #[cfg(feature = "warning-messages")]
+            let warning_logs = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Warn);
+
+            let info_logs = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Info);
+
+            #[cfg(feature = "warning-messages")]
+            assert_eq!(warning_logs.clone().count(), 4);
+            assert_eq!(info_logs.count(), 5);
+            #[cfg(feature = "warning-messages")]
+            {
+                let ignored_warnings: Vec<_> = warning_logs
+                    .filter(|log| log.body.contains("Ignored"))
+                    .collect();
+                assert_eq!(ignored_warnings.len(), 2);
+                assert_eq!(
+                    "3",
+                    ignored_warnings[0].body.split_whitespace().nth(1).unwrap()
+                );
+                assert_eq!(
+                    "3",
+                    ignored_warnings[1].body.split_whitespace().nth(1).unwrap()
+                );
+            }
+        })
+    }
+
+    #[test]
+    fn thread_local_logger_limits_correctly() {
+        logger_limits_correctly(LoggerVariant::TheadLocal);
+    }
+
+    #[test]
+    fn shared_logger_limits_correctly() {
+        logger_limits_correctly(LoggerVariant::Shared);
+    }
+
+    const ACCEPTABLE_DROP_FACTOR: f64 = 0.99;
+    const TEST_TIME_MS: usize = 500;
+    const TEST_PERIOD_MS: usize = 1;
+    const MAX_LOGS_PER_PERIOD: usize = 500;
+    #[cfg(feature = "warning-messages")]
+    const MAX_EXPECTED_WARNING_LOGS_PER_PERIOD: usize = 2;
+    fn spamming_does_not_work(spam_logs: impl Fn()) {
+        crate::testing_logger::setup();
+        spam_logs();
+        crate::testing_logger::validate(|captured_logs| {
+            #[cfg(feature = "warning-messages")]
+            let warning_logs = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Warn);
+
+            let info_logs = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Info);
+
+            #[cfg(feature = "warning-messages")]
+            let warning_logs_count = warning_logs.count();
+            let info_logs_count = info_logs.count();
+
+            // Ensure we don't overstep the limit on average
+            #[cfg(feature = "warning-messages")]
+            assert!(
+                warning_logs_count
+                    <= TEST_TIME_MS / TEST_PERIOD_MS * MAX_EXPECTED_WARNING_LOGS_PER_PERIOD
+            );
+            assert!(info_logs_count <= MAX_LOGS_PER_PERIOD * TEST_TIME_MS);
+
+            #[cfg(feature = "warning-messages")]
+            assert!(
+                warning_logs_count as f64
+                    > ((TEST_TIME_MS / TEST_PERIOD_MS * MAX_EXPECTED_WARNING_LOGS_PER_PERIOD)
+                        as f64
+                        * ACCEPTABLE_DROP_FACTOR)
+            );
+            assert!(
+                info_logs_count as f64
+                    > ((MAX_LOGS_PER_PERIOD * TEST_TIME_MS) as f64 * ACCEPTABLE_DROP_FACTOR)
+            );
+        })
+    }
+
+    #[test]
+    fn thread_local_spamming_does_not_work() {
+        spamming_does_not_work(|| {
+            let start = Instant::now();
+            while Instant::now().duration_since(start) < Duration::from_millis(TEST_TIME_MS as u64)
+            {
+                info_limit!(
+                    MAX_LOGS_PER_PERIOD,
+                    Duration::from_millis(TEST_PERIOD_MS as u64),
+                    "Logging on repeat"
+                );
+            }
+        })
+    }
+
+    #[test]
+    fn sync_spamming_does_not_work_sync() {
+        spamming_does_not_work(|| {
+            let start = Box::new(Instant::now());
+            let start = Box::leak(start);
+            let handles: Vec<_> = (0..1)
+                .map(|_| {
+                    std::thread::spawn(|| {
+                        while Instant::now().duration_since(*start)
+                            < Duration::from_millis(TEST_TIME_MS as u64)
+                        {
+                            info_limit_global!(
+                                MAX_LOGS_PER_PERIOD,
+                                Duration::from_millis(TEST_PERIOD_MS as u64),
+                                "Logging on repeat"
+                            );
+                        }
+                    })
+                })
+                .collect();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+        })
+    }
+
+    fn emissions_on_first_calls(config: LimiterConfig, calls: usize) -> usize {
+        let mut rate_limiter = RateLimiter::with_config(config);
+        let emitted = Cell::new(0);
+        for _ in 0..calls {
+            rate_limiter.log_maybe(Duration::from_secs(60), 10, || {
+                emitted.set(emitted.get() + 1)
+            });
+        }
+        emitted.get()
+    }
+
+    #[test]
+    fn first_call_is_emitted_by_default() {
+        assert_eq!(emissions_on_first_calls(LimiterConfig::default(), 1), 1);
+    }
+
+    #[test]
+    fn skip_first_only_primes_the_window() {
+        let config = LimiterConfig {
+            skip_first: true,
+            ..LimiterConfig::default()
+        };
+        assert_eq!(emissions_on_first_calls(config, 1), 0);
+        assert_eq!(emissions_on_first_calls(config, 2), 1);
+    }
+
+    #[test]
+    fn manual_clock_drives_window_rollover() {
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+        for _ in 0..5 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 2, log);
+            synchronised_rate_limiter.log_maybe(Duration::from_secs(1), 2, log);
+        }
+        assert_eq!(emitted.get(), 4);
+
+        clock.advance(Duration::from_millis(1001));
+        rate_limiter.log_maybe(Duration::from_secs(1), 2, log);
+        synchronised_rate_limiter.log_maybe(Duration::from_secs(1), 2, log);
+        assert_eq!(emitted.get(), 6);
+    }
+
+    #[test]
+    fn restored_snapshot_continues_accounting() {
+        let clock = ManualClock::new();
+        let period = Duration::from_secs(1);
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        for _ in 0..5 {
+            rate_limiter.log_maybe(period, 2, log);
+        }
+        clock.advance(Duration::from_millis(500));
+        let snapshot = rate_limiter.snapshot();
+        assert_eq!(snapshot.count, 2);
+        assert_eq!(snapshot.suppressed, 3);
+
+        let mut reloaded = RateLimiter::with_clock(clock.clone());
+        reloaded.restore(snapshot);
+        reloaded.log_maybe(period, 2, log);
+        assert_eq!(emitted.get(), 2);
+        assert_eq!(reloaded.snapshot().suppressed, 4);
+
+        clock.advance(Duration::from_millis(501));
+        reloaded.log_maybe(period, 2, log);
+        assert_eq!(emitted.get(), 3);
+        assert_eq!(reloaded.snapshot().count, 1);
+        assert_eq!(reloaded.snapshot().suppressed, 0);
+    }
+
+    #[test]
+    fn over_budget_messages_are_demoted() {
+        crate::testing_logger::setup();
+        let config = LimiterConfig {
+            demote_to: Some(log::Level::Trace),
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_config(config);
+        for _ in 0..5 {
+            let context = LogContext {
+                level: log::Level::Info,
+                target: module_path!(),
+            };
+            rate_limiter.log_maybe_with_context(Duration::from_secs(60), 2, &context, |level| {
+                log::log!(level, "Demotable")
+            });
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            let count = |level| {
+                captured_logs
+                    .iter()
+                    .filter(|log| log.level == level)
+                    .count()
+            };
+            assert_eq!(count(log::Level::Info), 2);
+            assert_eq!(count(log::Level::Trace), 3);
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn subscribers_receive_each_window_as_it_rolls_over() {
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let mut receiver = rate_limiter.subscribe();
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        }
+        assert!(!receiver.has_changed().unwrap());
+
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        receiver.changed().await.unwrap();
+        let snapshot = *receiver.borrow_and_update();
+        assert_eq!((snapshot.count, snapshot.suppressed), (1, 2));
+    }
+
+    #[test]
+    fn grace_windows_allow_more_before_the_cap_applies() {
+        let period = Duration::from_secs(1);
+        for (grace_max_per_time, expected) in [(Some(5), [5, 5, 2, 2]), (None, [10, 10, 2, 2])] {
+            let clock = ManualClock::new();
+            let config = LimiterConfig {
+                grace_windows: 2,
+                grace_max_per_time,
+                ..LimiterConfig::default()
+            };
+            let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+            let emitted = Cell::new(0);
+            let per_window = expected.map(|_| {
+                emitted.set(0);
+                for _ in 0..10 {
+                    rate_limiter.log_maybe(period, 2, || emitted.set(emitted.get() + 1));
+                }
+                clock.advance(Duration::from_millis(1100));
+                emitted.get()
+            });
+            assert_eq!(per_window, expected, "{grace_max_per_time:?}");
+        }
+    }
+
+    #[test]
+    fn quiet_window_carries_budget_into_burst() {
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            carryover_max: 5,
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        let period = Duration::from_secs(1);
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        rate_limiter.log_maybe(period, 10, log);
+        rate_limiter.log_maybe(period, 10, log);
+        clock.advance(Duration::from_millis(1100));
+        emitted.set(0);
+        for _ in 0..20 {
+            rate_limiter.log_maybe(period, 10, log);
+        }
+        // Capped at 5 of the 8 unused.
+        assert_eq!(emitted.get(), 15);
+
+        // The carried over budget was spent and nothing is carried into the next window.
+        clock.advance(Duration::from_millis(1100));
+        emitted.set(0);
+        for _ in 0..20 {
+            rate_limiter.log_maybe(period, 10, log);
+        }
+        assert_eq!(emitted.get(), 10);
+    }
+
+    #[test]
+    fn synchronised_rate_limiter_is_shareable_between_threads() {
+        let rate_limiter =
+            std::sync::Arc::new(SynchronisedRateLimiter::with_clock(ManualClock::new()));
+        let emitted = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let rate_limiter = rate_limiter.clone();
+                let emitted = emitted.clone();
+                thread::spawn(move || {
+                    for _ in 0..10 {
+                        rate_limiter.log_maybe(Duration::from_secs(60), 5, || {
+                            emitted.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        });
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(emitted.load(std::sync::atomic::Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn concurrent_accounting_adds_up() {
+        let rate_limiter = SynchronisedRateLimiter::with_clock(ManualClock::new());
+        let emitted = std::sync::atomic::AtomicUsize::new(0);
+        thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..1000 {
+                        rate_limiter.log_maybe(Duration::from_secs(60), 5, || {
+                            emitted.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        });
+                    }
+                });
+            }
+        });
+        assert_eq!(emitted.into_inner(), 5);
+        let snapshot = rate_limiter.snapshot();
+        assert_eq!((snapshot.count, snapshot.suppressed), (5, 7995));
+    }
+
+    #[test]
+    fn rate_limiter_can_be_moved_to_another_thread() {
+        let mut rate_limiter = RateLimiter::new();
+        let emitted = thread::spawn(move || {
+            let emitted = Cell::new(0);
+            rate_limiter.log_maybe(Duration::from_secs(60), 1, || emitted.set(1));
+            emitted.get()
+        });
+        assert_eq!(emitted.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn flood_panics_past_the_threshold_naming_the_call_site() {
+        let config = LimiterConfig {
+            panic_on_flood: Some(3),
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(ManualClock::new(), config);
+        let calls = std::cell::Cell::new(0);
+        let line = line!() + 3;
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| loop {
+            calls.set(calls.get() + 1);
+            rate_limiter.log_maybe(Duration::from_secs(1), 2, || {});
+        }))
+        .unwrap_err();
+        // Two emitted and three suppressed before the fourth suppression panics.
+        assert_eq!(calls.get(), 6);
+        let message = panic.downcast_ref::<String>().unwrap();
+        assert!(
+            message.contains(&format!("{}:{line}:", file!())),
+            "{message}"
+        );
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn ignored_message_names_the_target() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        let context = LogContext {
+            level: log::Level::Info,
+            target: "my_app::db",
+        };
+        for _ in 0..3 {
+            rate_limiter.log_maybe_with_context(Duration::from_secs(1), 1, &context, |_| {});
+            synchronised_rate_limiter.log_maybe_with_context(
+                Duration::from_secs(1),
+                1,
+                &context,
+                || {},
+            );
+        }
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe_with_context(Duration::from_secs(1), 1, &context, |_| {});
+        synchronised_rate_limiter.log_maybe_with_context(
+            Duration::from_secs(1),
+            1,
+            &context,
+            || {},
+        );
+        crate::testing_logger::validate(|captured_logs| {
+            let summaries: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("Ignored"))
+                .map(|log| &log.body[..36])
+                .collect();
+            assert_eq!(summaries, ["Ignored 2 logs from my_app::db since"; 2]);
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn context_prefixes_the_warnings() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        rate_limiter.set_context("billing".to_owned());
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        }
+        rate_limiter.set_context("billing/eu".to_owned());
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        crate::testing_logger::validate(|captured_logs| {
+            let bodies: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("billing"))
+                .map(|log| &log.body[..32])
+                .collect();
+            assert_eq!(
+                bodies,
+                [
+                    "billing: Hit logging threshold! ",
+                    "billing/eu: Ignored 2 logs since"
+                ]
+            );
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn too_short_periods_are_hinted_at_once() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            hint_misconfiguration: true,
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        // A budget above 1 never saturates at this cadence, so its windows never roll over.
+        let mut larger_rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        let mut unhinted_rate_limiter = RateLimiter::with_clock(clock.clone());
+        let emitted = Cell::new(0);
+        for _ in 0..20 {
+            rate_limiter.log_maybe(Duration::from_millis(10), 1, || {
+                emitted.set(emitted.get() + 1)
+            });
+            larger_rate_limiter.log_maybe(Duration::from_millis(10), 5, || {
+                emitted.set(emitted.get() + 1)
+            });
+            unhinted_rate_limiter.log_maybe(Duration::from_millis(10), 1, || {});
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(emitted.get(), 40);
+        crate::testing_logger::validate(|captured_logs| {
+            let hints: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("The previous log's last"))
+                .map(|log| log.body.as_str())
+                .collect();
+            assert_eq!(
+                hints,
+                ["The previous log's last 8 windows each saw a single call, so it isn't being limited. Its period of 10.0ms is likely shorter than the time between calls; consider a longer one"; 2]
+            );
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn ignored_message_uses_friendly_duration() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        }
+        clock.advance(Duration::from_nanos(1_501_234_567));
+        rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        crate::testing_logger::validate(|captured_logs| {
+            assert_eq!(
+                captured_logs.last().unwrap().body,
+                "Ignored 2 logs since 1.5s ago, emitting 1 of 3 (33.3%). Observed ~2.0 logs/sec, capped at 1.0/sec. Starting to log again..."
+            );
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn summary_shows_the_emitted_ratio() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let emitted = std::cell::Cell::new(0);
+        for _ in 0..152 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 10, || {
+                emitted.set(emitted.get() + 1)
+            });
+        }
+        assert_eq!(emitted.get(), 10);
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe(Duration::from_secs(1), 10, || {});
+        crate::testing_logger::validate(|captured_logs| {
+            assert!(captured_logs.iter().any(|log| log
+                .body
+                .starts_with("Ignored 142 logs since 2.0s ago, emitting 10 of 152 (6.6%).")));
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn shadow_summary_shows_the_ratio_that_would_have_been_emitted() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            shadow: true,
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        let emitted = std::cell::Cell::new(0);
+        for _ in 0..20 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 5, || emitted.set(emitted.get() + 1));
+        }
+        assert_eq!(emitted.get(), 20);
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe(Duration::from_secs(1), 5, || {});
+        crate::testing_logger::validate(|captured_logs| {
+            assert!(captured_logs.iter().any(|log| log.body.starts_with(
+                "Would have ignored 15 logs since 2.0s ago, would have emitted 5 of 20 (25.0%)."
+            )));
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn ignored_message_reports_observed_rate() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        // 120 logs per second against a cap of 10 per second.
+        for _ in 0..120 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 10, || {});
+            clock.advance(Duration::from_micros(8_400));
+        }
+        rate_limiter.log_maybe(Duration::from_secs(1), 10, || {});
+        crate::testing_logger::validate(|captured_logs| {
+            let summary = &captured_logs.last().unwrap().body;
+            let observed: f64 = summary
+                .split('~')
+                .nth(1)
+                .and_then(|rest| rest.split_whitespace().next())
+                .unwrap()
+                .parse()
+                .unwrap();
+            assert!((115.0..=125.0).contains(&observed), "{summary}");
+            assert!(summary.contains("capped at 10/sec"), "{summary}");
+        });
+    }
+
+    #[cfg(feature = "overhead-timing")]
+    #[test]
+    fn overhead_accumulates_across_calls() {
+        let mut rate_limiter = RateLimiter::new();
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(super::SystemClock);
+        for _ in 0..1000 {
+            rate_limiter.log_maybe(Duration::from_secs(60), 10, || {});
+            synchronised_rate_limiter.log_maybe(Duration::from_secs(60), 10, || {});
+        }
+        let first = rate_limiter.overhead_nanos();
+        assert!(first > 0);
+        assert!(synchronised_rate_limiter.overhead_nanos() > 0);
+        for _ in 0..1000 {
+            rate_limiter.log_maybe(Duration::from_secs(60), 10, || {});
+        }
+        assert!(rate_limiter.overhead_nanos() > first);
+    }
+
+    #[cfg(all(feature = "warning-messages", feature = "kv"))]
+    #[test]
+    fn ignored_message_carries_suppressed_count_as_key_value() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        for _ in 0..5 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+            synchronised_rate_limiter.log_maybe(Duration::from_secs(1), 2, || {});
+        }
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        synchronised_rate_limiter.log_maybe(Duration::from_secs(1), 2, || {});
+        crate::testing_logger::validate(|captured_logs| {
+            let suppressed: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| !log.key_values.is_empty())
+                .map(|log| log.key_values.clone())
+                .collect();
+            assert_eq!(
+                suppressed,
+                [
+                    vec![("suppressed".to_owned(), "4".to_owned())],
+                    vec![("suppressed".to_owned(), "3".to_owned())],
+                ]
+            );
+        });
+    }
+
+    #[cfg(feature = "wasm")]
+    #[test]
+    fn wasm_clock_is_used_as_the_instant_source() {
+        let clock = ManualClock::new();
+        let start: web_time::Instant = super::Clock::now(&clock);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(
+            super::Clock::now(&clock).duration_since(start),
+            Duration::from_secs(3)
+        );
+    }
+
+    #[test]
+    fn disabled_levels_do_not_consume_budget() {
+        crate::testing_logger::setup();
+        fn log_twice() {
+            for _ in 0..2 {
+                info_limit!(3, Duration::from_secs(60), "Maybe enabled");
+                info_limit_global!(3, Duration::from_secs(60), "Maybe enabled");
+            }
+        }
+        // Toggling the level at runtime, the budget of 3 is only used up while info is enabled.
+        log::set_max_level(log::LevelFilter::Warn);
+        log_twice();
+        log::set_max_level(log::LevelFilter::Trace);
+        log_twice();
+        log::set_max_level(log::LevelFilter::Warn);
+        log_twice();
+        log::set_max_level(log::LevelFilter::Trace);
+        log_twice();
+        crate::testing_logger::validate(|captured_logs| {
+            let info_logs = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Info);
+            assert_eq!(info_logs.count(), 6);
+        });
+    }
+
+    #[test]
+    fn should_log_respects_the_cap() {
+        let allowed = (0..10)
+            .filter(|_| should_log!(3, Duration::from_secs(60)))
+            .count();
+        assert_eq!(allowed, 3);
+    }
+
+    #[test]
+    fn release_limit_only_limits_release_builds() {
+        crate::testing_logger::setup();
+        for _ in 0..5 {
+            release_limit!(
+                log::Level::Info,
+                2,
+                Duration::from_secs(60),
+                "Release limited"
+            );
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            let info_logs = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Info);
+            #[cfg(debug_assertions)]
+            assert_eq!(info_logs.count(), 5);
+            #[cfg(not(debug_assertions))]
+            assert_eq!(info_logs.count(), 2);
+        });
+    }
+
+    #[test]
+    fn forced_logs_always_emit_but_use_up_the_budget() {
+        crate::testing_logger::setup();
+        for i in 0..6 {
+            info_limit!(force: i % 2 == 1; 2, Duration::from_secs(60), "thread {i}");
+            info_limit_global!(force: i % 2 == 1; 2, Duration::from_secs(60), "global {i}");
+        }
+        info_limit!(force; 1, Duration::from_secs(60), "always");
+        crate::testing_logger::validate(|captured_logs| {
+            let info_logs: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.level == log::Level::Info)
+                .collect();
+            // The forced logs 1, 3 and 5 always emit, while forced log 1 uses up the second
+            // slot of the budget, leaving only log 0 for the normal calls.
+            assert_eq!(info_logs.len(), 9);
+            #[cfg(feature = "warning-messages")]
+            {
+                let bodies: Vec<_> = info_logs.iter().map(|log| log.body.as_str()).collect();
+                assert_eq!(
+                    bodies,
+                    [
+                        "thread 0", "global 0", "thread 1", "global 1", "thread 3", "global 3",
+                        "thread 5", "global 5", "always",
+                    ]
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn counted_variants_return_the_suppressed_count() {
+        crate::testing_logger::setup();
+        let dropped: Vec<_> = (0..10)
+            .map(|_| info_limit_counted!(3, Duration::from_secs(60), "Counted"))
+            .collect();
+        assert_eq!(dropped, [0, 0, 0, 1, 2, 3, 4, 5, 6, 7]);
+        error_limit_counted!(1, Duration::from_millis(1), "");
+        warn_limit_counted!(1, Duration::from_millis(1), "");
+        debug_limit_counted!(1, Duration::from_millis(1), "");
+        trace_limit_counted!(1, Duration::from_millis(1), "");
+    }
+
+    #[test]
+    fn limit_scope_shares_one_budget() {
+        crate::testing_logger::setup();
+        for i in 0..5 {
+            limit_scope!(3, Duration::from_secs(60); {
+                info!("Step {i}");
+                debug!("Detail {i}");
+                if i == 4 {
+                    error!("Done");
                 }
+            });
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            let limited: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.level != log::Level::Warn)
+                .map(|log| log.level)
+                .collect();
+            assert_eq!(
+                limited,
+                [log::Level::Info, log::Level::Debug, log::Level::Info]
+            );
+        });
+    }
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn per_instance_limits_give_each_closure_a_budget() {
+        crate::testing_logger::setup();
+        let handler = |name: &'static str| {
+            let mut rate_limiter = RateLimiter::new();
+            move || {
+                per_instance_limit!(
+                    rate_limiter,
+                    log::Level::Info,
+                    2,
+                    Duration::from_secs(60),
+                    "instance {name}"
+                )
             }
-            thread::sleep(Duration::from_millis(11));
-            // 00: Log
-            // 11: Log (and warn of omission)
-            // 22: Omit
-            // 33: Omit
-            // 44: Omit
-            // 55: Log (and warn: missed 3)
-            // 66: Log (and warn of omission)
-            // 77: Omit
-            // 88: Omit
-            // 99: Omit
-            // 110: Log (and warn: missed 3)
+        };
+        let (mut first, mut second) = (handler("first"), handler("second"));
+        for _ in 0..5 {
+            first();
+            second();
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            let emitted: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("instance"))
+                .map(|log| log.body.as_str())
+                .collect();
+            // Both closures come from the same source yet neither uses up the other's budget.
+            assert_eq!(
+                emitted,
+                [
+                    "instance first",
+                    "instance second",
+                    "instance first",
+                    "instance second"
+                ]
+            );
+        });
+    }
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn periods_can_be_given_as_strings() {
+        crate::testing_logger::setup();
+        for i in 0..5 {
+            info_limit!(2, "1h", "string period {i}");
+            info_limit_global!(1, "30s", "string period global {i}");
+        }
+        let _ = info_limit_counted!(1, "500ms", "");
+        crate::hybrid_limit!(log::Level::Info, 1, "1s", "");
+        info_limit!(1, crate::period!("10m"), "");
+        let _ = should_log!(1, "5m");
+        eprint_limit!(1, "2h", "");
+        limit_scope!(1, "1m"; {
+            debug!("");
+        });
+        crate::testing_logger::validate(|captured_logs| {
+            let emitted: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("string period"))
+                .map(|log| log.body.as_str())
+                .collect();
+            assert_eq!(
+                emitted,
+                [
+                    "string period 0",
+                    "string period global 0",
+                    "string period 1"
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn all_synchronised_variants_compile() {
+        error_limit_global!(1, Duration::from_millis(1), "");
+        warn_limit_global!(1, Duration::from_millis(1), "");
+        info_limit_global!(1, Duration::from_millis(1), "");
+        debug_limit_global!(1, Duration::from_millis(1), "");
+        trace_limit_global!(1, Duration::from_millis(1), "");
+    }
+
+    #[test]
+    fn all_thread_variants_compile() {
+        error_limit!(1, Duration::from_millis(1), "");
+        warn_limit!(1, Duration::from_millis(1), "");
+        info_limit!(1, Duration::from_millis(1), "");
+        debug_limit!(1, Duration::from_millis(1), "");
+        trace_limit!(1, Duration::from_millis(1), "");
+    }
+
+    #[test]
+    fn poll_summarises_idle_call_sites() {
+        crate::testing_logger::setup();
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        for _ in 0..10 {
+            rate_limiter.log_maybe(period, 2, || {});
+            synchronised_rate_limiter.log_maybe(period, 2, || {});
         }
+        // Nothing to summarise until the window is over.
+        rate_limiter.poll();
+        synchronised_rate_limiter.poll();
+        clock.advance(period * 2);
+        rate_limiter.poll();
+        synchronised_rate_limiter.poll();
+        // Already summarised.
+        rate_limiter.poll();
+        synchronised_rate_limiter.poll();
+
         crate::testing_logger::validate(|captured_logs| {
-            #[cfg(feature = "warning-messages")]
-            let warning_logs = captured_logs
+            let warnings = captured_logs
                 .iter()
                 .filter(|log| log.level == log::Level::Warn);
-
-            let info_logs = captured_logs
-                .iter()
-                .filter(|log| log.level == log::Level::Info);
-
-            #[cfg(feature = "warning-messages")]
-            assert_eq!(warning_logs.clone().count(), 4);
-            assert_eq!(info_logs.count(), 5);
+            // A threshold warning and a summary each.
             #[cfg(feature = "warning-messages")]
+            assert_eq!(warnings.count(), 4);
+            #[cfg(not(feature = "warning-messages"))]
+            assert_eq!(warnings.count(), 0);
+            #[cfg(all(
+                feature = "warning-messages",
+                not(any(feature = "json-meta", feature = "logfmt-meta"))
+            ))]
             {
-                let ignored_warnings: Vec<_> = warning_logs
-                    .filter(|log| log.body.contains("Ignored"))
+                let summaries: Vec<_> = captured_logs
+                    .iter()
+                    .filter(|log| log.body.starts_with("Ignored"))
+                    .map(|log| &log.body[..15])
                     .collect();
-                assert_eq!(ignored_warnings.len(), 2);
-                assert_eq!(
-                    "3",
-                    ignored_warnings[0].body.split_whitespace().nth(1).unwrap()
-                );
-                assert_eq!(
-                    "3",
-                    ignored_warnings[1].body.split_whitespace().nth(1).unwrap()
-                );
+                assert_eq!(summaries, ["Ignored 8 logs ", "Ignored 8 logs "]);
             }
-        })
+        });
+        assert_eq!(rate_limiter.snapshot().count, 0);
+        assert_eq!(synchronised_rate_limiter.snapshot().count, 0);
     }
 
     #[test]
-    fn thread_local_logger_limits_correctly() {
-        logger_limits_correctly(LoggerVariant::TheadLocal);
+    fn shadow_mode_emits_everything_but_counts_the_suppressions() {
+        let config = LimiterConfig {
+            shadow: true,
+            ..LimiterConfig::default()
+        };
+        let period = Duration::from_secs(1);
+        let mut rate_limiter = RateLimiter::with_clock_and_config(ManualClock::new(), config);
+        let emitted = Cell::new(0);
+        for _ in 0..10 {
+            rate_limiter.log_maybe(period, 3, || emitted.set(emitted.get() + 1));
+        }
+        assert_eq!(emitted.get(), 10);
+        assert_eq!(rate_limiter.snapshot().suppressed, 7);
     }
 
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
     #[test]
-    fn shared_logger_limits_correctly() {
-        logger_limits_correctly(LoggerVariant::Shared);
-    }
-
-    const ACCEPTABLE_DROP_FACTOR: f64 = 0.99;
-    const TEST_TIME_MS: usize = 500;
-    const TEST_PERIOD_MS: usize = 1;
-    const MAX_LOGS_PER_PERIOD: usize = 500;
-    #[cfg(feature = "warning-messages")]
-    const MAX_EXPECTED_WARNING_LOGS_PER_PERIOD: usize = 2;
-    fn spamming_does_not_work(spam_logs: impl Fn()) {
+    fn recovery_notice_uses_the_configured_level() {
         crate::testing_logger::setup();
-        spam_logs();
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            recovery_level: Some(log::Level::Info),
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        let log_errors = |rate_limiter: &mut RateLimiter<ManualClock>, count| {
+            for _ in 0..count {
+                rate_limiter.log_maybe(period, 2, || log::error!("Still broken"));
+            }
+            clock.advance(period * 2);
+        };
+
+        // A flood, then a window within the budget, then another.
+        log_errors(&mut rate_limiter, 5);
+        log_errors(&mut rate_limiter, 1);
+        log_errors(&mut rate_limiter, 1);
+        log_errors(&mut rate_limiter, 1);
+
         crate::testing_logger::validate(|captured_logs| {
-            #[cfg(feature = "warning-messages")]
-            let warning_logs = captured_logs
+            let notices: Vec<_> = captured_logs
                 .iter()
-                .filter(|log| log.level == log::Level::Warn);
+                .filter(|log| log.body.starts_with("Flood subsided"))
+                .collect();
+            assert_eq!(notices.len(), 1);
+            assert_eq!(notices[0].level, log::Level::Info);
+        });
+    }
 
-            let info_logs = captured_logs
-                .iter()
-                .filter(|log| log.level == log::Level::Info);
+    #[test]
+    fn a_panicking_log_leaves_the_synchronised_limiter_usable() {
+        use std::panic;
+        use std::panic::AssertUnwindSafe;
 
-            #[cfg(feature = "warning-messages")]
-            let warning_logs_count = warning_logs.count();
-            let info_logs_count = info_logs.count();
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        rate_limiter.log_maybe(period, 1, || {});
+        rate_limiter.log_maybe(period, 1, || {});
+        clock.advance(period * 2);
+        // The rollover, which takes the lock, logs through a panicking `log`.
+        let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+            rate_limiter.log_maybe(period, 1, || panic!("the logger broke"));
+        }));
+        assert!(panicked.is_err());
 
-            // Ensure we don't overstep the limit on average
-            #[cfg(feature = "warning-messages")]
-            assert!(
-                warning_logs_count
-                    <= TEST_TIME_MS / TEST_PERIOD_MS * MAX_EXPECTED_WARNING_LOGS_PER_PERIOD
-            );
-            assert!(info_logs_count <= MAX_LOGS_PER_PERIOD * TEST_TIME_MS);
+        let emitted = Cell::new(0);
+        rate_limiter.log_maybe(period, 1, || emitted.set(emitted.get() + 1));
+        assert_eq!(emitted.get(), 0);
+        let snapshot = rate_limiter.snapshot();
+        assert_eq!((snapshot.count, snapshot.suppressed), (1, 1));
+        clock.advance(period * 2);
+        rate_limiter.poll();
+        rate_limiter.log_maybe(period, 1, || emitted.set(emitted.get() + 1));
+        assert_eq!(emitted.get(), 1);
+    }
 
-            #[cfg(feature = "warning-messages")]
-            assert!(
-                warning_logs_count as f64
-                    > ((TEST_TIME_MS / TEST_PERIOD_MS * MAX_EXPECTED_WARNING_LOGS_PER_PERIOD)
-                        as f64
-                        * ACCEPTABLE_DROP_FACTOR)
+    #[test]
+    fn limiters_below_their_cap_are_never_throttled() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        let mut flooded_rate_limiter = RateLimiter::with_clock(clock.clone());
+        let flooded_synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        for _ in 0..4 {
+            rate_limiter.log_maybe(period, 5, || {});
+            synchronised_rate_limiter.log_maybe(period, 5, || {});
+            flooded_rate_limiter.log_maybe(period, 1, || {});
+            flooded_synchronised_rate_limiter.log_maybe(period, 1, || {});
+            // Not a full window yet.
+            assert!(!rate_limiter.never_throttled());
+            assert!(!synchronised_rate_limiter.never_throttled());
+            clock.advance(period / 4);
+        }
+        assert!(rate_limiter.never_throttled());
+        assert!(synchronised_rate_limiter.never_throttled());
+        assert!(!flooded_rate_limiter.never_throttled());
+        assert!(!flooded_synchronised_rate_limiter.never_throttled());
+    }
+
+    #[test]
+    fn window_hooks_see_each_window() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let started_at = clock.now();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let starts = events.clone();
+        rate_limiter
+            .on_window_start(move |snapshot| starts.lock().unwrap().push(("start", snapshot)));
+        let ends = events.clone();
+        rate_limiter.on_window_end(move |snapshot| ends.lock().unwrap().push(("end", snapshot)));
+
+        for _ in 0..5 {
+            rate_limiter.log_maybe(period, 2, || {});
+        }
+        clock.advance(period * 2);
+        for _ in 0..3 {
+            rate_limiter.log_maybe(period, 2, || {});
+        }
+
+        let snapshot = |count, suppressed, window_start| LimiterSnapshot {
+            count,
+            suppressed,
+            window_start,
+        };
+        assert_eq!(
+            *events.lock().unwrap(),
+            [
+                ("start", snapshot(0, 0, started_at)),
+                ("end", snapshot(2, 3, started_at)),
+                ("start", snapshot(0, 0, clock.now())),
+            ]
+        );
+        assert_eq!(rate_limiter.snapshot(), snapshot(2, 1, clock.now()));
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn approaching_the_threshold_is_noticed_once() {
+        crate::testing_logger::setup();
+        let config = LimiterConfig {
+            approaching_percent: Some(80),
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(ManualClock::new(), config);
+        for _ in 0..20 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 10, || {});
+        }
+
+        crate::testing_logger::validate(|captured_logs| {
+            let notices: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("Used up 80%"))
+                .map(|log| log.body.as_str())
+                .collect();
+            assert_eq!(
+                notices,
+                ["Used up 80% of the logging budget, 2 more logs until the previous log is ignored"]
             );
-            assert!(
-                info_logs_count as f64
-                    > ((MAX_LOGS_PER_PERIOD * TEST_TIME_MS) as f64 * ACCEPTABLE_DROP_FACTOR)
+        });
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn approaching_the_synchronised_threshold_is_noticed_once_per_window() {
+        crate::testing_logger::setup();
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let rate_limiter =
+            SynchronisedRateLimiter::with_clock(clock.clone()).with_approaching_percent(70);
+        for _ in 0..2 {
+            for _ in 0..20 {
+                rate_limiter.log_maybe(period, 10, || {});
+            }
+            clock.advance(period * 2);
+        }
+
+        crate::testing_logger::validate(|captured_logs| {
+            let notices: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("Used up 70%"))
+                .map(|log| log.body.as_str())
+                .collect();
+            assert_eq!(
+                notices,
+                ["Used up 70% of the logging budget, 3 more logs until the previous log is ignored";
+                    2]
             );
-        })
+        });
     }
 
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
     #[test]
-    fn thread_local_spamming_does_not_work() {
-        spamming_does_not_work(|| {
-            let start = Instant::now();
-            while Instant::now().duration_since(start) < Duration::from_millis(TEST_TIME_MS as u64)
-            {
-                info_limit!(
-                    MAX_LOGS_PER_PERIOD,
-                    Duration::from_millis(TEST_PERIOD_MS as u64),
-                    "Logging on repeat"
+    fn sustained_floods_are_escalated_once() {
+        crate::testing_logger::setup();
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            escalate_after: Some(3),
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        let escalations = || {
+            let escalations = std::cell::Cell::new(0);
+            crate::testing_logger::validate(|captured_logs| {
+                escalations.set(
+                    captured_logs
+                        .iter()
+                        .filter(|log| log.body.starts_with("Sustained flooding!"))
+                        .count(),
                 );
+            });
+            escalations.get()
+        };
+        let mut per_window = Vec::new();
+        // A transient burst, followed by a quiet period.
+        for _ in 0..5 {
+            rate_limiter.log_maybe(period, 2, || {});
+        }
+        clock.advance(period * 3);
+        // Then a sustained flood.
+        for _ in 0..6 {
+            for _ in 0..5 {
+                rate_limiter.log_maybe(period, 2, || {});
             }
-        })
+            per_window.push(escalations());
+            clock.advance(period * 3 / 2);
+        }
+        // Each window is summarised by the first call of the next, so the third saturated window
+        // of the flood is escalated in the fourth.
+        assert_eq!(per_window, [0, 0, 0, 1, 0, 0]);
     }
 
     #[test]
-    fn sync_spamming_does_not_work_sync() {
-        spamming_does_not_work(|| {
-            let start = Box::new(Instant::now());
-            let start = Box::leak(start);
-            let handles: Vec<_> = (0..1)
-                .map(|_| {
-                    std::thread::spawn(|| {
-                        while Instant::now().duration_since(*start)
-                            < Duration::from_millis(TEST_TIME_MS as u64)
-                        {
-                            info_limit_global!(
-                                MAX_LOGS_PER_PERIOD,
-                                Duration::from_millis(TEST_PERIOD_MS as u64),
-                                "Logging on repeat"
-                            );
-                        }
-                    })
-                })
+    fn seeded_jitter_gives_reproducible_windows() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone())
+            .with_jitter_seeded(Duration::from_millis(500), 42);
+        let starts = Arc::new(Mutex::new(Vec::new()));
+        let hook_starts = starts.clone();
+        rate_limiter.on_window_start(move |snapshot| {
+            hook_starts.lock().unwrap().push(snapshot.window_start)
+        });
+        for _ in 0..1000 {
+            rate_limiter.log_maybe(period, 1, || {});
+            clock.advance(Duration::from_millis(10));
+        }
+
+        let starts = starts.lock().unwrap();
+        let lengths: Vec<_> = starts
+            .windows(2)
+            .map(|pair| pair[1].duration_since(pair[0]).as_millis())
+            .collect();
+        // Each window lasts the period plus up to 500ms, rounded up to the next call.
+        assert_eq!(lengths, [1400, 1230, 1190, 1060, 1240, 1140, 1220, 1450]);
+    }
+
+    #[test]
+    fn jitter_on_the_longest_period_does_not_overflow() {
+        let mut rate_limiter = RateLimiter::with_clock(ManualClock::new())
+            .with_jitter_seeded(Duration::from_secs(1), 1);
+        let emitted = Cell::new(0);
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::MAX, 1, || emitted.set(emitted.get() + 1));
+        }
+        assert_eq!(emitted.get(), 1);
+    }
+
+    #[test]
+    fn time_until_the_next_log_shrinks_within_a_saturated_window() {
+        let period = Duration::from_secs(10);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        assert_eq!(rate_limiter.next_allowed_in(period, 2), None);
+        for _ in 0..2 {
+            rate_limiter.log_maybe(period, 2, || {});
+        }
+
+        let mut waits = Vec::new();
+        for _ in 0..3 {
+            waits.push(rate_limiter.next_allowed_in(period, 2));
+            clock.advance(Duration::from_secs(4));
+        }
+        assert_eq!(
+            waits,
+            [
+                Some(Duration::from_secs(10)),
+                Some(Duration::from_secs(6)),
+                Some(Duration::from_secs(2)),
+            ]
+        );
+        assert_eq!(rate_limiter.next_allowed_in(period, 2), None);
+        let emitted = std::cell::Cell::new(false);
+        rate_limiter.log_maybe(period, 2, || emitted.set(true));
+        assert!(emitted.get());
+    }
+
+    #[test]
+    fn time_until_the_next_log_includes_the_jitter() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone())
+            .with_jitter_seeded(Duration::from_millis(500), 42);
+        rate_limiter.log_maybe(period, 1, || {});
+
+        let wait = rate_limiter.next_allowed_in(period, 1).unwrap();
+        assert!(wait > period, "{wait:?}");
+        clock.advance(wait);
+        assert_eq!(
+            rate_limiter.next_allowed_in(period, 1),
+            Some(Duration::from_nanos(1))
+        );
+        let emitted = Cell::new(0);
+        rate_limiter.log_maybe(period, 1, || emitted.set(emitted.get() + 1));
+        assert_eq!(emitted.get(), 0);
+
+        clock.advance(Duration::from_nanos(1));
+        assert_eq!(rate_limiter.next_allowed_in(period, 1), None);
+        rate_limiter.log_maybe(period, 1, || emitted.set(emitted.get() + 1));
+        assert_eq!(emitted.get(), 1);
+    }
+
+    #[test]
+    fn paused_time_does_not_end_the_window() {
+        let period = Duration::from_secs(10);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let emitted = std::cell::Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+        rate_limiter.log_maybe(period, 1, log);
+        clock.advance(Duration::from_secs(5));
+
+        rate_limiter.pause();
+        clock.advance(Duration::from_secs(60));
+        rate_limiter.log_maybe(period, 1, log);
+        rate_limiter.resume();
+        rate_limiter.log_maybe(period, 1, log);
+        // Only 5s of the window have passed, paused time aside.
+        assert_eq!(emitted.get(), 1);
+        assert_eq!(rate_limiter.snapshot().suppressed, 2);
+
+        clock.advance(Duration::from_secs(6));
+        rate_limiter.log_maybe(period, 1, log);
+        assert_eq!(emitted.get(), 2);
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn resets_clear_all_per_window_state() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            escalate_after: Some(5),
+            ..LimiterConfig::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        for _ in 0..2 {
+            for _ in 0..3 {
+                rate_limiter.log_maybe(period, 1, || {});
+            }
+            clock.advance(period * 2);
+        }
+        rate_limiter.log_maybe(period, 1, || {});
+        rate_limiter.pause();
+        assert_eq!(rate_limiter.saturated_windows, 2);
+        assert!(rate_limiter.limits.is_some());
+
+        rate_limiter.reset();
+        assert_eq!(rate_limiter.saturated_windows, 0);
+        assert_eq!(rate_limiter.limits, None);
+        assert_eq!(rate_limiter.paused_at, None);
+        assert_eq!(rate_limiter.snapshot().window_start, clock.now());
+        assert!(!rate_limiter.never_throttled());
+
+        let rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        for _ in 0..3 {
+            rate_limiter.log_maybe(period, 1, || {});
+        }
+        rate_limiter.reset();
+        assert_eq!(*rate_limiter.limits.lock().unwrap(), None);
+        assert_eq!(
+            rate_limiter
+                .period_nanos
+                .load(std::sync::atomic::Ordering::Relaxed),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn signalled_resets_give_each_burst_a_fresh_budget() {
+        let period = Duration::from_secs(60);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let emitted = std::cell::Cell::new(0);
+        let mut per_burst = Vec::new();
+        for _ in 0..3 {
+            rate_limiter.signal_reset();
+            emitted.set(0);
+            for _ in 0..10 {
+                rate_limiter.log_maybe(period, 3, || emitted.set(emitted.get() + 1));
+            }
+            per_burst.push(emitted.get());
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(per_burst, [3, 3, 3]);
+        assert_eq!(rate_limiter.snapshot().suppressed, 7);
+    }
+
+    #[test]
+    fn the_last_suppressed_message_is_emitted_at_rollover() {
+        crate::testing_logger::setup();
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let config = LimiterConfig {
+            emit_last_suppressed: true,
+            ..Default::default()
+        };
+        let mut rate_limiter = RateLimiter::with_clock_and_config(clock.clone(), config);
+        let context = LogContext {
+            level: log::Level::Error,
+            target: "last_suppressed",
+        };
+        for i in 0..5 {
+            rate_limiter.log_message(period, 2, &context, format_args!("state {i}"));
+        }
+        clock.advance(period * 2);
+        rate_limiter.log_message(period, 2, &context, format_args!("state 5"));
+
+        crate::testing_logger::validate(|captured_logs| {
+            let emitted: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.target == "last_suppressed")
+                .map(|log| log.level)
                 .collect();
-            for handle in handles {
-                handle.join().unwrap();
+            assert_eq!(emitted, [log::Level::Error; 4]);
+            #[cfg(feature = "warning-messages")]
+            {
+                let bodies: Vec<_> = captured_logs
+                    .iter()
+                    .filter(|log| log.target == "last_suppressed")
+                    .map(|log| log.body.as_str())
+                    .collect();
+                assert_eq!(bodies, ["state 0", "state 1", "state 4", "state 5"]);
             }
-        })
+        });
     }
 
     #[test]
-    fn all_synchronised_variants_compile() {
-        error_limit_global!(1, Duration::from_millis(1), "");
-        warn_limit_global!(1, Duration::from_millis(1), "");
-        info_limit_global!(1, Duration::from_millis(1), "");
-        debug_limit_global!(1, Duration::from_millis(1), "");
-        trace_limit_global!(1, Duration::from_millis(1), "");
+    fn about_the_sampling_rate_of_decisions_are_recorded() {
+        // Windows end with the first call after their period, so at every 1000th call.
+        let period = Duration::from_millis(999);
+        let clock = ManualClock::new();
+        let start = clock.now();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let decisions = Arc::new(Mutex::new(Vec::new()));
+        let recorded = decisions.clone();
+        rate_limiter.on_decision_sampled_seeded(0.1, 42, move |now, decision| {
+            recorded.lock().unwrap().push((now, decision))
+        });
+        // 10 windows of 1000 calls, each emitting 100.
+        for _ in 0..10_000 {
+            rate_limiter.log_maybe(period, 100, || {});
+            clock.advance(Duration::from_millis(1));
+        }
+
+        // The same seed picks the same calls.
+        let mut sampler = crate::sampling::DecisionSampler::new(0.1, 42, Box::new(|_, _| {}));
+        let expected: Vec<_> = (0..10_000u64)
+            .filter(|_| sampler.sample())
+            .map(|call| {
+                // The first call only primes the limiter, the first of later windows rolls over.
+                let decision = match (call, call % 1000) {
+                    (0, _) => Decision::Suppress,
+                    (_, 0) => Decision::Rollover,
+                    (_, 1..100) | (1..1000, 100) => Decision::Emit,
+                    _ => Decision::Suppress,
+                };
+                (start + Duration::from_millis(call), decision)
+            })
+            .collect();
+        assert_eq!(*decisions.lock().unwrap(), expected);
+        assert!((850..=1150).contains(&expected.len()), "{}", expected.len());
     }
 
     #[test]
-    fn all_thread_variants_compile() {
-        error_limit!(1, Duration::from_millis(1), "");
-        warn_limit!(1, Duration::from_millis(1), "");
-        info_limit!(1, Duration::from_millis(1), "");
-        debug_limit!(1, Duration::from_millis(1), "");
-        trace_limit!(1, Duration::from_millis(1), "");
+    fn total_calls_count_every_call_across_windows() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        let context = LogContext {
+            level: log::Level::Info,
+            target: "total_calls",
+        };
+        for _ in 0..4 {
+            for _ in 0..10 {
+                rate_limiter.log_maybe(period, 3, || {});
+                rate_limiter.log_maybe_with_context(period, 3, &context, |_| {});
+                synchronised_rate_limiter.log_maybe(period, 3, || {});
+            }
+            rate_limiter.log_maybe_with(|_| Decision::Suppress, || {});
+            rate_limiter.log_forced(|| {});
+            clock.advance(period * 2);
+        }
+        // Forced logs aren't decided on, so they aren't counted.
+        assert_eq!(rate_limiter.total_calls(), 84);
+        assert_eq!(synchronised_rate_limiter.total_calls(), 40);
+    }
+
+    #[test]
+    fn counts_near_usize_max_saturate_rather_than_wrap() {
+        let period = Duration::from_secs(60);
+        let rate_limiter = SynchronisedRateLimiter::with_clock(ManualClock::new());
+        rate_limiter.set_count(usize::MAX - 2);
+        let emitted = Cell::new(0);
+        for _ in 0..10 {
+            rate_limiter.log_maybe(period, 5, || emitted.set(emitted.get() + 1));
+        }
+        rate_limiter.log_forced(|| {});
+        // Wrapping around would have let the calls after the third through as within the budget.
+        assert_eq!(emitted.get(), 0);
+        assert_eq!(rate_limiter.snapshot().count, super::MAX_COUNT - 10);
+    }
+
+    #[test]
+    fn merged_limiters_share_their_accounting() {
+        let period = Duration::from_secs(10);
+        let clock = ManualClock::new();
+        let started_at = clock.now();
+        let mut first = RateLimiter::with_clock(clock.clone());
+        for _ in 0..6 {
+            first.log_maybe(period, 4, || {});
+        }
+        clock.advance(Duration::from_secs(3));
+        let mut second = RateLimiter::with_clock(clock.clone());
+        second.log_maybe(period, 4, || {});
+
+        second.merge(&first);
+        assert_eq!(
+            second.snapshot(),
+            LimiterSnapshot {
+                count: 5,
+                suppressed: 2,
+                window_start: started_at,
+            }
+        );
+        assert_eq!(second.age(), Duration::from_secs(3));
+        // The combined budget is used up until the earlier window ends.
+        let emitted = Cell::new(0);
+        second.log_maybe(period, 4, || emitted.set(emitted.get() + 1));
+        assert_eq!(emitted.get(), 0);
+        clock.advance(Duration::from_secs(8));
+        second.log_maybe(period, 4, || emitted.set(emitted.get() + 1));
+        assert_eq!(emitted.get(), 1);
+    }
+
+    #[test]
+    fn age_grows_across_windows() {
+        let period = Duration::from_secs(1);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        let mut ages = Vec::new();
+        for _ in 0..5 {
+            for _ in 0..3 {
+                rate_limiter.log_maybe(period, 1, || {});
+                synchronised_rate_limiter.log_maybe(period, 1, || {});
+            }
+            ages.push((rate_limiter.age(), synchronised_rate_limiter.age()));
+            clock.advance(period * 2);
+        }
+        let expected: Vec<_> = (0..5)
+            .map(|window| (period * 2 * window, period * 2 * window))
+            .collect();
+        assert_eq!(ages, expected);
+        assert_eq!(
+            rate_limiter.snapshot().window_start,
+            clock.now() - period * 2
+        );
+    }
+
+    #[test]
+    fn large_periods_do_not_overflow() {
+        let day = Duration::from_secs(24 * 60 * 60);
+        let period = 100 * day;
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+        let mut carryover_rate_limiter = RateLimiter::with_clock_and_config(
+            clock.clone(),
+            LimiterConfig {
+                carryover_max: usize::MAX,
+                ..LimiterConfig::default()
+            },
+        );
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+        let mut log_all = |period| {
+            for _ in 0..3 {
+                rate_limiter.log_maybe(period, 1, log);
+                synchronised_rate_limiter.log_maybe(period, 1, log);
+                carryover_rate_limiter.log_maybe(period, 1, log);
+            }
+        };
+
+        log_all(period);
+        assert_eq!(emitted.get(), 3);
+        clock.advance(50 * day);
+        log_all(period);
+        assert_eq!(emitted.get(), 3);
+        clock.advance(51 * day);
+        log_all(period);
+        assert_eq!(emitted.get(), 6);
+        log_all(Duration::MAX);
+        assert_eq!(emitted.get(), 6);
+    }
+
+    #[test]
+    fn custom_policy_decides_per_call() {
+        let period = Duration::from_secs(10);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let emitted_at = RefCell::new(Vec::new());
+        // Emit only in the first half of each window.
+        let first_half = |state: &LimiterState| {
+            if state.elapsed >= period {
+                Decision::Rollover
+            } else if state.elapsed < period / 2 {
+                Decision::Emit
+            } else {
+                Decision::Suppress
+            }
+        };
+        for second in 0..25 {
+            rate_limiter.log_maybe_with(first_half, || emitted_at.borrow_mut().push(second));
+            clock.advance(Duration::from_secs(1));
+        }
+        assert_eq!(
+            emitted_at.into_inner(),
+            [0, 1, 2, 3, 4, 10, 11, 12, 13, 14, 20, 21, 22, 23, 24]
+        );
+        assert_eq!(rate_limiter.snapshot().count, 5);
+    }
+
+    proptest! {
+        #[test]
+        fn fixed_window_bounds_hold(
+            max_per_time in 1usize..5,
+            period_ms in 1u64..50,
+            advances_ms in proptest::collection::vec(0u64..30, 1..200),
+        ) {
+            let period = Duration::from_millis(period_ms);
+            let clock = ManualClock::new();
+            let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+            let mut now = Duration::ZERO;
+            let mut emitted_at = Vec::new();
+            let mut suppressed_in_window = 0;
+            let mut reported = 0;
+            let mut previous = rate_limiter.snapshot();
+
+            for advance_ms in advances_ms.iter().copied() {
+                clock.advance(Duration::from_millis(advance_ms));
+                now += Duration::from_millis(advance_ms);
+                let emitted = Cell::new(false);
+                rate_limiter.log_maybe(period, max_per_time, || emitted.set(true));
+
+                let snapshot = rate_limiter.snapshot();
+                if snapshot.window_start != previous.window_start {
+                    prop_assert_eq!(previous.suppressed, suppressed_in_window);
+                    reported += previous.suppressed;
+                    suppressed_in_window = 0;
+                }
+                if emitted.get() {
+                    emitted_at.push(now);
+                } else {
+                    suppressed_in_window += 1;
+                }
+                prop_assert_eq!(snapshot.suppressed, suppressed_in_window);
+                previous = snapshot;
+            }
+
+            prop_assert_eq!(emitted_at.len() + reported + suppressed_in_window, advances_ms.len());
+            for (i, start) in emitted_at.iter().enumerate() {
+                let in_span = emitted_at[i..]
+                    .iter()
+                    .take_while(|at| **at < *start + period)
+                    .count();
+                prop_assert!(in_span <= 2 * max_per_time);
+            }
+        }
+    }
+
+    /// A snapshot racing with a rollover sees the window either before or after it.
+    #[cfg(loom)]
+    #[test]
+    fn loom_snapshots_never_mix_two_windows() {
+        loom::model(|| {
+            let period = Duration::from_secs(1);
+            let clock = ManualClock::new();
+            let limiter = loom::sync::Arc::new(SynchronisedRateLimiter::with_clock(clock.clone()));
+            limiter.log_maybe(period, 1, || {});
+            limiter.log_maybe(period, 1, || {});
+            clock.advance(period * 2);
+            let rolling_over = limiter.clone();
+            let rollover = loom::thread::spawn(move || rolling_over.log_maybe(period, 1, || {}));
+            let snapshot = limiter.snapshot();
+            rollover.join().unwrap();
+            let counts = (snapshot.count, snapshot.suppressed);
+            // Before the rollover, with or without its call counted, or after it.
+            assert!([(1, 1), (2, 1), (1, 0)].contains(&counts), "{counts:?}");
+        });
+    }
+
+    /// A snapshot racing with a suppressed call never sees the suppression without the call.
+    #[cfg(loom)]
+    #[test]
+    fn loom_snapshots_never_see_a_suppression_before_its_call() {
+        loom::model(|| {
+            let period = Duration::from_secs(1);
+            let limiter =
+                loom::sync::Arc::new(SynchronisedRateLimiter::with_clock(ManualClock::new()));
+            limiter.log_maybe(period, 1, || {});
+            let suppressing = limiter.clone();
+            let suppressed = loom::thread::spawn(move || suppressing.log_maybe(period, 1, || {}));
+            let snapshot = limiter.snapshot();
+            suppressed.join().unwrap();
+            let counts = (snapshot.count, snapshot.suppressed);
+            // Before the call, after its increment of the count, or after its suppression.
+            assert!([(1, 0), (2, 0), (1, 1)].contains(&counts), "{counts:?}");
+        });
     }
 }
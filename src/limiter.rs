@@ -0,0 +1,442 @@
+use crate::AdaptivePeriodLimiter;
+use crate::AlignedLimiter;
+use crate::Clock;
+use crate::EmaLimiter;
+use crate::ExponentialLimiter;
+use crate::FixedLimiter;
+use crate::Health;
+use crate::HealthAwareLimiter;
+use crate::HierarchicalLimiter;
+use crate::HybridLimiter;
+use crate::LeakyBucketLimiter;
+use crate::RateLimiter;
+use crate::SharedLimiter;
+#[cfg(all(feature = "shared-memory", unix))]
+use crate::SharedMemoryLimiter;
+use crate::SynchronisedRateLimiter;
+use crate::SystemClock;
+use crate::ThreadSlicedLimiter;
+use crate::TickLimiter;
+use std::cell::Cell;
+#[cfg(all(feature = "shared-memory", unix))]
+use std::io;
+use std::time::Duration;
+
+/// A rate limiting strategy, usable as `Box<dyn Limiter>` so the algorithm can be picked at
+/// runtime.
+pub trait Limiter {
+    /// Calls `log` unless the strategy decides it should be suppressed.
+    fn log_maybe(&mut self, log: &dyn Fn());
+}
+
+/// A [`RateLimiter`] bundled with its `period` and `max_per_time`, i.e. the fixed window strategy
+/// used by the `*_limit!` macros.
+pub struct FixedWindowLimiter<C: Clock = SystemClock> {
+    rate_limiter: RateLimiter<C>,
+    period: Duration,
+    max_per_time: usize,
+}
+
+impl FixedWindowLimiter {
+    pub fn new(max_per_time: usize, period: Duration) -> Self {
+        Self::with_rate_limiter(RateLimiter::new(), max_per_time, period)
+    }
+}
+
+impl<C: Clock> FixedWindowLimiter<C> {
+    pub fn with_rate_limiter(
+        rate_limiter: RateLimiter<C>,
+        max_per_time: usize,
+        period: Duration,
+    ) -> Self {
+        Self {
+            rate_limiter,
+            period,
+            max_per_time,
+        }
+    }
+}
+
+impl<C: Clock> Limiter for FixedWindowLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        self.rate_limiter
+            .log_maybe(self.period, self.max_per_time, log);
+    }
+}
+
+/// A [`SynchronisedRateLimiter`] bundled with its `period` and `max_per_time`, i.e. the strategy
+/// used by the `*_limit_global!` macros.
+pub struct SynchronisedWindowLimiter<C: Clock = SystemClock> {
+    rate_limiter: SynchronisedRateLimiter<C>,
+    period: Duration,
+    max_per_time: usize,
+}
+
+impl SynchronisedWindowLimiter {
+    pub fn new(max_per_time: usize, period: Duration) -> Self {
+        Self::with_rate_limiter(
+            SynchronisedRateLimiter::with_clock(SystemClock),
+            max_per_time,
+            period,
+        )
+    }
+}
+
+impl<C: Clock> SynchronisedWindowLimiter<C> {
+    pub fn with_rate_limiter(
+        rate_limiter: SynchronisedRateLimiter<C>,
+        max_per_time: usize,
+        period: Duration,
+    ) -> Self {
+        Self {
+            rate_limiter,
+            period,
+            max_per_time,
+        }
+    }
+}
+
+impl<C: Clock> Limiter for SynchronisedWindowLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        self.rate_limiter
+            .log_maybe(self.period, self.max_per_time, log);
+    }
+}
+
+/// A [`ThreadSlicedLimiter`] bundled with its `period`, `max_per_time` and `max_per_thread`.
+pub struct ThreadSlicedWindowLimiter<C: Clock + Clone = SystemClock> {
+    limiter: ThreadSlicedLimiter<C>,
+    period: Duration,
+    max_per_time: usize,
+    max_per_thread: usize,
+}
+
+impl ThreadSlicedWindowLimiter {
+    pub fn new(
+        max_threads: usize,
+        max_per_time: usize,
+        max_per_thread: usize,
+        period: Duration,
+    ) -> Self {
+        Self::with_limiter(
+            ThreadSlicedLimiter::new(max_threads),
+            max_per_time,
+            max_per_thread,
+            period,
+        )
+    }
+}
+
+impl<C: Clock + Clone> ThreadSlicedWindowLimiter<C> {
+    pub fn with_limiter(
+        limiter: ThreadSlicedLimiter<C>,
+        max_per_time: usize,
+        max_per_thread: usize,
+        period: Duration,
+    ) -> Self {
+        Self {
+            limiter,
+            period,
+            max_per_time,
+            max_per_thread,
+        }
+    }
+}
+
+impl<C: Clock + Clone> Limiter for ThreadSlicedWindowLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        self.limiter
+            .log_maybe(self.period, self.max_per_time, self.max_per_thread, log);
+    }
+}
+
+/// A [`SharedMemoryLimiter`] bundled with its `period` and `max_per_time`.
+#[cfg(all(feature = "shared-memory", unix))]
+pub struct SharedMemoryWindowLimiter {
+    limiter: SharedMemoryLimiter,
+    period: Duration,
+    max_per_time: usize,
+}
+
+#[cfg(all(feature = "shared-memory", unix))]
+impl SharedMemoryWindowLimiter {
+    pub fn new(max_per_time: usize, period: Duration) -> io::Result<Self> {
+        Ok(Self::with_limiter(
+            SharedMemoryLimiter::new()?,
+            max_per_time,
+            period,
+        ))
+    }
+
+    pub fn with_limiter(
+        limiter: SharedMemoryLimiter,
+        max_per_time: usize,
+        period: Duration,
+    ) -> Self {
+        Self {
+            limiter,
+            period,
+            max_per_time,
+        }
+    }
+}
+
+#[cfg(all(feature = "shared-memory", unix))]
+impl Limiter for SharedMemoryWindowLimiter {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        self.limiter.log_maybe(self.period, self.max_per_time, log);
+    }
+}
+
+impl<L: Limiter + ?Sized> Limiter for Box<L> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        L::log_maybe(self, log);
+    }
+}
+
+/// Logs only if both limiters permit it, e.g. to combine a short and a long window. Every call is
+/// charged to both.
+pub struct AndLimiter<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Limiter, B: Limiter> AndLimiter<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: Limiter, B: Limiter> Limiter for AndLimiter<A, B> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        let (first, second) = permits(&mut self.first, &mut self.second);
+        if first && second {
+            log();
+        }
+    }
+}
+
+/// Logs if either limiter permits it. Every call is charged to both.
+pub struct OrLimiter<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A: Limiter, B: Limiter> OrLimiter<A, B> {
+    pub fn new(first: A, second: B) -> Self {
+        Self { first, second }
+    }
+}
+
+impl<A: Limiter, B: Limiter> Limiter for OrLimiter<A, B> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        let (first, second) = permits(&mut self.first, &mut self.second);
+        if first || second {
+            log();
+        }
+    }
+}
+
+/// Charges a call to both limiters, returning which of them would have logged it.
+fn permits(first: &mut impl Limiter, second: &mut impl Limiter) -> (bool, bool) {
+    let (first_permits, second_permits) = (Cell::new(false), Cell::new(false));
+    first.log_maybe(&|| first_permits.set(true));
+    second.log_maybe(&|| second_permits.set(true));
+    (first_permits.get(), second_permits.get())
+}
+
+impl<C: Clock> Limiter for HybridLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        HybridLimiter::log_maybe(self, log);
+    }
+}
+
+impl<C: Clock> Limiter for SharedLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        SharedLimiter::log_maybe(self, log);
+    }
+}
+
+impl<const MAX: usize, C: Clock> Limiter for FixedLimiter<MAX, C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        FixedLimiter::log_maybe(self, log);
+    }
+}
+
+impl<F: Fn() -> Health, C: Clock> Limiter for HealthAwareLimiter<F, C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        HealthAwareLimiter::log_maybe(self, log);
+    }
+}
+
+impl<C: Clock> Limiter for HierarchicalLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        HierarchicalLimiter::log_maybe(self, log);
+    }
+}
+
+impl<C: Clock> Limiter for EmaLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        EmaLimiter::log_maybe(self, log);
+    }
+}
+
+impl<C: Clock> Limiter for AdaptivePeriodLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        AdaptivePeriodLimiter::log_maybe(self, log);
+    }
+}
+
+impl<C: Clock> Limiter for LeakyBucketLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        LeakyBucketLimiter::log_maybe(self, log);
+    }
+}
+
+impl<C: Clock> Limiter for AlignedLimiter<C> {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        AlignedLimiter::log_maybe(self, log);
+    }
+}
+
+impl Limiter for TickLimiter {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        TickLimiter::log_maybe(self, log);
+    }
+}
+
+impl Limiter for ExponentialLimiter {
+    fn log_maybe(&mut self, log: &dyn Fn()) {
+        ExponentialLimiter::log_maybe(self, log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AndLimiter;
+    use super::FixedWindowLimiter;
+    use super::Limiter;
+    use super::OrLimiter;
+    #[cfg(all(feature = "shared-memory", unix))]
+    use super::SharedMemoryWindowLimiter;
+    use super::SynchronisedWindowLimiter;
+    use super::ThreadSlicedWindowLimiter;
+    use crate::HybridLimiter;
+    use crate::ManualClock;
+    use crate::RateLimiter;
+    use crate::SharedLimiter;
+    use crate::SharedLimiterConfig;
+    use crate::SynchronisedRateLimiter;
+    use crate::ThreadSlicedLimiter;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    fn limiter_from_config(strategy: &str, clock: &ManualClock) -> Box<dyn Limiter> {
+        match strategy {
+            "fixed-window" => Box::new(FixedWindowLimiter::with_rate_limiter(
+                RateLimiter::with_clock(clock.clone()),
+                3,
+                Duration::from_secs(1),
+            )),
+            "hybrid" => Box::new(HybridLimiter::with_clock(
+                clock.clone(),
+                2,
+                Duration::from_secs(1),
+            )),
+            "shared" => Box::new(SharedLimiter::with_clock(
+                clock.clone(),
+                SharedLimiterConfig::new(4, Duration::from_secs(1)),
+            )),
+            "synchronised" => Box::new(SynchronisedWindowLimiter::with_rate_limiter(
+                SynchronisedRateLimiter::with_clock(clock.clone()),
+                5,
+                Duration::from_secs(1),
+            )),
+            "thread-sliced" => Box::new(ThreadSlicedWindowLimiter::with_limiter(
+                ThreadSlicedLimiter::with_clock(clock.clone(), 4),
+                8,
+                6,
+                Duration::from_secs(1),
+            )),
+            #[cfg(all(feature = "shared-memory", unix))]
+            "shared-memory" => {
+                Box::new(SharedMemoryWindowLimiter::new(7, Duration::from_secs(60)).unwrap())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn strategies_are_interchangeable_behind_a_trait_object() {
+        let clock = ManualClock::new();
+        let emitted = Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+
+        let strategies = [
+            ("fixed-window", 3),
+            ("hybrid", 2),
+            ("shared", 4),
+            ("synchronised", 5),
+            ("thread-sliced", 6),
+            #[cfg(all(feature = "shared-memory", unix))]
+            ("shared-memory", 7),
+        ];
+        for (strategy, expected) in strategies {
+            let mut limiter = limiter_from_config(strategy, &clock);
+            emitted.set(0);
+            for _ in 0..10 {
+                limiter.log_maybe(&log);
+            }
+            assert_eq!(emitted.get(), expected, "{strategy}");
+        }
+    }
+
+    /// Makes five calls every two seconds for ten seconds, returning how many were logged.
+    fn emitted_over_ten_seconds(clock: &ManualClock, limiter: &mut impl Limiter) -> usize {
+        let emitted = Cell::new(0);
+        for _ in 0..5 {
+            for _ in 0..5 {
+                limiter.log_maybe(&|| emitted.set(emitted.get() + 1));
+            }
+            clock.advance(Duration::from_secs(2));
+        }
+        emitted.get()
+    }
+
+    fn short_window(clock: &ManualClock) -> FixedWindowLimiter<ManualClock> {
+        let rate_limiter = RateLimiter::with_clock(clock.clone());
+        FixedWindowLimiter::with_rate_limiter(rate_limiter, 2, Duration::from_secs(1))
+    }
+
+    fn long_window(clock: &ManualClock) -> FixedWindowLimiter<ManualClock> {
+        let rate_limiter = RateLimiter::with_clock(clock.clone());
+        FixedWindowLimiter::with_rate_limiter(rate_limiter, 3, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn and_is_more_restrictive() {
+        let clock = ManualClock::new();
+        assert_eq!(
+            emitted_over_ten_seconds(&clock, &mut short_window(&clock)),
+            10
+        );
+        assert_eq!(
+            emitted_over_ten_seconds(&clock, &mut long_window(&clock)),
+            3
+        );
+        let mut limiter = AndLimiter::new(short_window(&clock), long_window(&clock));
+        // Only the first two calls pass both windows.
+        assert_eq!(emitted_over_ten_seconds(&clock, &mut limiter), 2);
+    }
+
+    #[test]
+    fn or_is_less_restrictive() {
+        let clock = ManualClock::new();
+        let mut limiter = OrLimiter::new(
+            Box::new(short_window(&clock)) as Box<dyn Limiter>,
+            Box::new(long_window(&clock)) as Box<dyn Limiter>,
+        );
+        // The long window lets a third call through during the first two seconds.
+        assert_eq!(emitted_over_ten_seconds(&clock, &mut limiter), 11);
+    }
+}
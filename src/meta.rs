@@ -0,0 +1,654 @@
+use crate::Instant;
+use std::fmt;
+use std::sync::Mutex;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// A message the limiters log about their own behaviour, see [`set_meta_formatter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MetaEvent {
+    /// `percent` of the budget was used up, see
+    /// [`LimiterConfig::approaching_percent`](crate::LimiterConfig::approaching_percent).
+    #[non_exhaustive]
+    Approaching { percent: u8 },
+    /// The budget was used up, so the following logs will be ignored until the window rolls over.
+    Threshold,
+    /// The window rolled over after ignoring `count` logs over `duration`.
+    #[non_exhaustive]
+    Ignored { count: usize, duration: Duration },
+    /// A full window passed within the budget after a flood, see
+    /// [`LimiterConfig::recovery_level`](crate::LimiterConfig::recovery_level).
+    Recovered,
+    /// Every window saw a single call for a while, so the period is likely shorter than the time
+    /// between calls, see
+    /// [`LimiterConfig::hint_misconfiguration`](crate::LimiterConfig::hint_misconfiguration).
+    Misconfigured,
+    /// `windows` windows in a row used up their budget, see
+    /// [`LimiterConfig::escalate_after`](crate::LimiterConfig::escalate_after).
+    #[non_exhaustive]
+    Sustained { windows: u32 },
+    /// Logs from `call_sites` call sites together exceeded the storm threshold, see
+    /// `storm::set_detector` with the `registry` feature.
+    #[non_exhaustive]
+    Storm { call_sites: usize },
+    /// A [`TickLimiter`](crate::TickLimiter) window rolled over after ignoring `count` logs over
+    /// `ticks` ticks.
+    #[non_exhaustive]
+    IgnoredTicks { count: usize, ticks: u64 },
+    /// `count` buffered logs are dumped ahead of an error, see
+    /// [`buffer::set_capacity`](crate::buffer::set_capacity).
+    #[non_exhaustive]
+    Dumping { count: usize },
+}
+
+type MetaFormatter = Box<dyn Fn(MetaEvent) -> String + Send + Sync>;
+
+static META_FORMATTER: RwLock<Option<MetaFormatter>> = RwLock::new(None);
+
+/// Replaces the wording of the threshold and ignored-count messages, e.g. to localise them.
+pub fn set_meta_formatter(formatter: impl Fn(MetaEvent) -> String + Send + Sync + 'static) {
+    *META_FORMATTER.write().unwrap() = Some(Box::new(formatter));
+}
+
+/// Restores the default wording of the threshold and ignored-count messages.
+pub fn clear_meta_formatter() {
+    *META_FORMATTER.write().unwrap() = None;
+}
+
+/// A fixed window cap on the meta messages of all limiters together, however often their
+/// windows roll over. Any over it are dropped, and counted for the next one admitted to report.
+struct Ceiling {
+    state: Mutex<CeilingState>,
+}
+
+struct CeilingState {
+    max_per_time: usize,
+    period: Duration,
+    /// The start of the current window and the meta messages logged in it.
+    window: Option<(Instant, usize)>,
+    /// Meta messages dropped since the last one admitted.
+    dropped: usize,
+}
+
+impl Ceiling {
+    const fn new(max_per_time: usize, period: Duration) -> Self {
+        Self {
+            state: Mutex::new(CeilingState {
+                max_per_time,
+                period,
+                window: None,
+                dropped: 0,
+            }),
+        }
+    }
+
+    /// Whether another meta message at `now` fits under the ceiling, counting it either way. If
+    /// so, returns the number dropped since the last one admitted.
+    fn admit(&self, now: Instant) -> Option<usize> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let admitted = match &mut state.window {
+            Some((start, count)) if now.duration_since(*start) <= state.period => {
+                *count = count.saturating_add(1);
+                *count <= state.max_per_time
+            }
+            window => {
+                *window = Some((now, 1));
+                state.max_per_time > 0
+            }
+        };
+        if admitted {
+            Some(std::mem::take(&mut state.dropped))
+        } else {
+            state.dropped = state.dropped.saturating_add(1);
+            None
+        }
+    }
+
+    fn set(&self, max_per_time: usize, period: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.max_per_time = max_per_time;
+        state.period = period;
+    }
+}
+
+static CEILING: Ceiling = Ceiling::new(5, Duration::from_secs(1));
+
+/// Caps the meta messages of all limiters together to `max_per_time` per `period`, dropping any
+/// over it. The next one admitted reports how many were dropped. Defaults to 5 per second.
+pub fn set_meta_ceiling(max_per_time: usize, period: Duration) {
+    CEILING.set(max_per_time, period);
+}
+
+/// Lifts the ceiling, as the unit tests share a process and their meta messages mustn't depend
+/// on each other's. tests/meta_ceiling.rs checks the ceiling itself.
+#[cfg(test)]
+pub(crate) fn lift_ceiling() {
+    CEILING.set(usize::MAX, Duration::from_secs(1));
+}
+
+/// Displays a meta message followed by the number of meta messages dropped over the ceiling
+/// before it, if any.
+struct WithDropped<T>(T, usize);
+
+impl<T: fmt::Display> fmt::Display for WithDropped<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)?;
+        if self.1 > 0 {
+            write!(
+                f,
+                " ({} earlier meta messages were dropped over the ceiling)",
+                self.1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Displays a [`Duration`] rounded to a single decimal in the largest fitting unit, e.g. `5.0s`
+/// rather than `5.001234567s`.
+pub(crate) struct FriendlyDuration(pub(crate) Duration);
+
+impl fmt::Display for FriendlyDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs_f64();
+        if secs >= 3600.0 {
+            write!(f, "{:.1}h", secs / 3600.0)
+        } else if secs >= 60.0 {
+            write!(f, "{:.1}m", secs / 60.0)
+        } else if secs >= 1.0 {
+            write!(f, "{secs:.1}s")
+        } else if secs >= 1e-3 {
+            write!(f, "{:.1}ms", secs * 1e3)
+        } else {
+            write!(f, "{}µs", self.0.as_micros())
+        }
+    }
+}
+
+/// Displays `count` logs per `duration` as a number of logs per second, rounded to whole logs
+/// from ten per second.
+pub(crate) struct FriendlyRate(pub(crate) usize, pub(crate) Duration);
+
+impl fmt::Display for FriendlyRate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let per_second = self.0 as f64 / self.1.as_secs_f64();
+        if per_second >= 10.0 {
+            write!(f, "{per_second:.0}")
+        } else {
+            write!(f, "{per_second:.1}")
+        }
+    }
+}
+
+/// Prefixes a message with the context of its limiter, see
+/// [`RateLimiter::set_context`](crate::RateLimiter::set_context).
+pub(crate) struct InContext<'a, M>(pub(crate) Option<&'a str>, pub(crate) M);
+
+impl<M: fmt::Display> fmt::Display for InContext<'_, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(context) = self.0 {
+            write!(f, "{context}: ")?;
+        }
+        self.1.fmt(f)
+    }
+}
+
+/// The summary logged when a window with suppressed logs rolls over.
+pub(crate) struct Ignored<'a> {
+    /// The target of the ignored logs, if known.
+    pub(crate) target: Option<&'a str>,
+    /// Suppressed logs in the window, or in shadow mode those that would have been.
+    pub(crate) count: usize,
+    pub(crate) since: Duration,
+    /// Emitted and suppressed logs in the window.
+    pub(crate) window_log_count: usize,
+    pub(crate) max_per_time: usize,
+    pub(crate) period: Duration,
+    /// Whether the limiter is in shadow mode, so emitted every log.
+    pub(crate) shadow: bool,
+}
+
+impl fmt::Display for Ignored<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.shadow {
+            write!(f, "Would have ignored {} logs ", self.count)?;
+        } else {
+            write!(f, "Ignored {} logs ", self.count)?;
+        }
+        if let Some(target) = self.target {
+            write!(f, "from {target} ")?;
+        }
+        // In shadow mode every log was emitted, so this is the share that would have been.
+        let emitted = self.window_log_count.saturating_sub(self.count);
+        write!(
+            f,
+            "since {} ago, {} {emitted} of {} ({:.1}%). Observed ~{} logs/sec, capped at {}/sec. Starting to log again...",
+            FriendlyDuration(self.since),
+            if self.shadow { "would have emitted" } else { "emitting" },
+            self.window_log_count,
+            emitted as f64 * 100.0 / self.window_log_count.max(1) as f64,
+            FriendlyRate(self.window_log_count, self.since),
+            FriendlyRate(self.max_per_time, self.period)
+        )
+    }
+}
+
+/// Displays a [`MetaEvent`] for the structured formats: as a compact JSON object, e.g.
+/// `{"log_limit":"ignored","count":3,"since_ms":1500}`, for the `json-meta` feature, or else as
+/// logfmt, e.g. `log_limit=ignored count=3 since=1.5s`, for the `logfmt-meta` feature. The
+/// target of the ignored logs is included when known.
+#[cfg(any(feature = "json-meta", feature = "logfmt-meta"))]
+struct Structured<'a> {
+    event: MetaEvent,
+    target: Option<&'a str>,
+    /// Meta messages dropped over the ceiling before this one.
+    dropped: usize,
+}
+
+#[cfg(any(feature = "json-meta", feature = "logfmt-meta"))]
+impl Structured<'_> {
+    fn name(&self) -> &'static str {
+        match self.event {
+            MetaEvent::Approaching { .. } => "approaching",
+            MetaEvent::Threshold => "threshold",
+            MetaEvent::Ignored { .. } => "ignored",
+            MetaEvent::Recovered => "recovered",
+            MetaEvent::Misconfigured => "misconfigured",
+            MetaEvent::Sustained { .. } => "sustained",
+            MetaEvent::Storm { .. } => "storm",
+            MetaEvent::IgnoredTicks { .. } => "ignored_ticks",
+            MetaEvent::Dumping { .. } => "dumping",
+        }
+    }
+}
+
+#[cfg(feature = "json-meta")]
+impl fmt::Display for Structured<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name();
+        write!(f, r#"{{"log_limit":"{name}""#)?;
+        if let MetaEvent::Approaching { percent } = self.event {
+            write!(f, r#","percent":{percent}"#)?;
+        }
+        if let MetaEvent::Sustained { windows } = self.event {
+            write!(f, r#","windows":{windows}"#)?;
+        }
+        if let MetaEvent::Storm { call_sites } = self.event {
+            write!(f, r#","call_sites":{call_sites}"#)?;
+        }
+        if let MetaEvent::IgnoredTicks { count, ticks } = self.event {
+            write!(f, r#","count":{count},"ticks":{ticks}"#)?;
+        }
+        if let MetaEvent::Dumping { count } = self.event {
+            write!(f, r#","count":{count}"#)?;
+        }
+        if let MetaEvent::Ignored { count, duration } = self.event {
+            write!(f, r#","count":{count},"since_ms":{}"#, duration.as_millis())?;
+        }
+        if let Some(target) = self.target {
+            // Module paths never need escaping, but arbitrary targets might.
+            write!(f, r#","target":"{}""#, JsonEscaped(target))?;
+        }
+        if self.dropped > 0 {
+            write!(f, r#","dropped":{}"#, self.dropped)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Displays a string escaped for use within a JSON string literal: quotes, backslashes and
+/// control characters are escaped, everything else is passed through as is.
+#[cfg(feature = "json-meta")]
+struct JsonEscaped<'a>(&'a str);
+
+#[cfg(feature = "json-meta")]
+impl fmt::Display for JsonEscaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str(r#"\""#)?,
+                '\\' => f.write_str(r"\\")?,
+                '\n' => f.write_str(r"\n")?,
+                '\r' => f.write_str(r"\r")?,
+                '\t' => f.write_str(r"\t")?,
+                c if c < '\u{20}' => write!(f, r"\u{:04x}", u32::from(c))?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays a string as a logfmt value: as is if it's a single bare token, or else quoted with
+/// quotes, backslashes and control characters escaped.
+#[cfg(all(feature = "logfmt-meta", not(feature = "json-meta")))]
+struct LogfmtValue<'a>(&'a str);
+
+#[cfg(all(feature = "logfmt-meta", not(feature = "json-meta")))]
+impl fmt::Display for LogfmtValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let needs_quoting = |c: char| matches!(c, ' ' | '=' | '"' | '\\') || c.is_control();
+        if !self.0.is_empty() && !self.0.contains(needs_quoting) {
+            return f.write_str(self.0);
+        }
+        f.write_str("\"")?;
+        for c in self.0.chars() {
+            match c {
+                '"' => f.write_str(r#"\""#)?,
+                '\\' => f.write_str(r"\\")?,
+                '\n' => f.write_str(r"\n")?,
+                '\r' => f.write_str(r"\r")?,
+                '\t' => f.write_str(r"\t")?,
+                c if c.is_control() => write!(f, r"\u{:04x}", u32::from(c))?,
+                c => write!(f, "{c}")?,
+            }
+        }
+        f.write_str("\"")
+    }
+}
+
+#[cfg(all(feature = "logfmt-meta", not(feature = "json-meta")))]
+impl fmt::Display for Structured<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.name();
+        write!(f, "log_limit={name}")?;
+        if let MetaEvent::Approaching { percent } = self.event {
+            write!(f, " percent={percent}")?;
+        }
+        if let MetaEvent::Sustained { windows } = self.event {
+            write!(f, " windows={windows}")?;
+        }
+        if let MetaEvent::Storm { call_sites } = self.event {
+            write!(f, " call_sites={call_sites}")?;
+        }
+        if let MetaEvent::IgnoredTicks { count, ticks } = self.event {
+            write!(f, " count={count} ticks={ticks}")?;
+        }
+        if let MetaEvent::Dumping { count } = self.event {
+            write!(f, " count={count}")?;
+        }
+        if let MetaEvent::Ignored { count, duration } = self.event {
+            write!(f, " count={count} since={}", FriendlyDuration(duration))?;
+        }
+        if let Some(target) = self.target {
+            write!(f, " target={}", LogfmtValue(target))?;
+        }
+        if self.dropped > 0 {
+            write!(f, " dropped={}", self.dropped)?;
+        }
+        Ok(())
+    }
+}
+
+/// Logs `event` at warn level, worded as `message` unless a custom formatter is set, see
+/// [`emit_at`].
+fn emit(event: MetaEvent, target: Option<&str>, message: impl fmt::Display) {
+    emit_at(log::Level::Warn, event, target, message);
+}
+
+/// Logs `event` about the logs from `target` at `level`, unless over the ceiling. It's worded as
+/// `message` unless a custom formatter is set, or structured with the `json-meta` or
+/// `logfmt-meta` feature, and reports the meta messages dropped over the ceiling before it. With
+/// the `kv` feature the count of ignored logs is also attached as a machine-readable `suppressed`
+/// field.
+#[cold]
+fn emit_at(level: log::Level, event: MetaEvent, target: Option<&str>, message: impl fmt::Display) {
+    let Some(dropped) = CEILING.admit(Instant::now()) else {
+        return;
+    };
+    #[cfg(any(feature = "json-meta", feature = "logfmt-meta"))]
+    let _ = message;
+    #[cfg(not(any(feature = "json-meta", feature = "logfmt-meta")))]
+    let _ = target;
+    let formatter = META_FORMATTER.read().unwrap();
+    let message: &dyn fmt::Display = match &*formatter {
+        Some(formatter) => &WithDropped(formatter(event), dropped),
+        #[cfg(any(feature = "json-meta", feature = "logfmt-meta"))]
+        None => &Structured {
+            event,
+            target,
+            dropped,
+        },
+        #[cfg(not(any(feature = "json-meta", feature = "logfmt-meta")))]
+        None => &WithDropped(message, dropped),
+    };
+    #[cfg(feature = "otel")]
+    if let MetaEvent::Ignored { count, .. } = event {
+        crate::otel::record_summary(count, target, message);
+    }
+    #[cfg(feature = "kv")]
+    if let MetaEvent::Ignored { count, .. } = event {
+        log::log!(target: "log_limit", level, suppressed = count; "{message}");
+        return;
+    }
+    log::log!(target: "log_limit", level, "{message}");
+}
+
+/// Logs that `percent` of the budget was used up.
+#[cold]
+pub(crate) fn warn_approaching(percent: u8, message: impl fmt::Display) {
+    emit(MetaEvent::Approaching { percent }, None, message);
+}
+
+/// Logs that the threshold was hit.
+#[cold]
+pub(crate) fn warn_threshold(message: impl fmt::Display) {
+    emit(MetaEvent::Threshold, None, message);
+}
+
+/// Logs a summary of `count` ignored logs from `target` over `duration`.
+#[cold]
+pub(crate) fn warn_ignored(
+    count: usize,
+    duration: Duration,
+    target: Option<&str>,
+    message: impl fmt::Display,
+) {
+    emit(MetaEvent::Ignored { count, duration }, target, message);
+}
+
+/// Logs at `level` that a flood subsided.
+#[cold]
+pub(crate) fn notify_recovered(level: log::Level, message: impl fmt::Display) {
+    emit_at(level, MetaEvent::Recovered, None, message);
+}
+
+/// Logs that `windows` windows in a row used up their budget.
+#[cold]
+pub(crate) fn warn_sustained(windows: u32, message: impl fmt::Display) {
+    emit(MetaEvent::Sustained { windows }, None, message);
+}
+
+/// Logs that a storm across `call_sites` call sites was detected.
+#[cfg(feature = "registry")]
+#[cold]
+pub(crate) fn warn_storm(call_sites: usize, message: impl fmt::Display) {
+    emit(MetaEvent::Storm { call_sites }, None, message);
+}
+
+/// Logs a summary of `count` logs ignored over `ticks` ticks.
+#[cold]
+pub(crate) fn warn_ignored_ticks(count: usize, ticks: u64, message: impl fmt::Display) {
+    emit(MetaEvent::IgnoredTicks { count, ticks }, None, message);
+}
+
+/// Logs that `count` buffered logs are dumped.
+#[cold]
+pub(crate) fn warn_dumping(count: usize, message: impl fmt::Display) {
+    emit(MetaEvent::Dumping { count }, None, message);
+}
+
+/// Hints that a limiter is misconfigured.
+#[cold]
+pub(crate) fn hint_misconfigured(message: impl fmt::Display) {
+    emit(MetaEvent::Misconfigured, None, message);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clear_meta_formatter;
+    use super::set_meta_formatter;
+    use super::FriendlyDuration;
+    use super::FriendlyRate;
+    use super::MetaEvent;
+    use crate::ManualClock;
+    use crate::RateLimiter;
+    use std::time::Duration;
+
+    #[test]
+    fn durations_are_rounded_to_the_largest_unit() {
+        let friendly = |duration| FriendlyDuration(duration).to_string();
+        assert_eq!(friendly(Duration::from_nanos(5_001_234_567)), "5.0s");
+        assert_eq!(friendly(Duration::from_micros(2_218_167)), "2.2s");
+        assert_eq!(friendly(Duration::from_micros(5_516)), "5.5ms");
+        assert_eq!(friendly(Duration::from_nanos(12_345)), "12µs");
+        assert_eq!(friendly(Duration::from_secs(90)), "1.5m");
+        assert_eq!(friendly(Duration::from_secs(9000)), "2.5h");
+    }
+
+    #[test]
+    fn rates_are_per_second() {
+        let friendly = |count, duration| FriendlyRate(count, duration).to_string();
+        assert_eq!(friendly(10, Duration::from_secs(1)), "10");
+        assert_eq!(friendly(7, Duration::from_secs(2)), "3.5");
+        assert_eq!(friendly(121, Duration::from_millis(1008)), "120");
+    }
+
+    #[test]
+    fn ceiling_drops_meta_messages_over_its_budget_and_counts_them() {
+        let ceiling = super::Ceiling::new(2, Duration::from_secs(1));
+        let now = crate::Instant::now();
+        let later = now + Duration::from_millis(1500);
+        let admitted = [now, now, now, now, later, later].map(|at| ceiling.admit(at));
+        assert_eq!(admitted, [Some(0), Some(0), None, None, Some(2), Some(0)]);
+    }
+
+    #[test]
+    fn the_ceiling_is_configurable() {
+        let ceiling = super::Ceiling::new(2, Duration::from_secs(1));
+        ceiling.set(1, Duration::from_secs(10));
+        let now = crate::Instant::now();
+        let later = now + Duration::from_secs(5);
+        let admitted = [now, later].map(|at| ceiling.admit(at));
+        assert_eq!(admitted, [Some(0), None]);
+    }
+
+    #[test]
+    fn custom_formatter_replaces_the_wording() {
+        crate::testing_logger::setup();
+        set_meta_formatter(|event| match event {
+            MetaEvent::Threshold => "Zu viele Logs!".to_owned(),
+            MetaEvent::Ignored {
+                count, duration, ..
+            } => {
+                format!("{count} Logs in {}s ignoriert", duration.as_secs())
+            }
+            MetaEvent::Recovered => "Wieder normal".to_owned(),
+            MetaEvent::Misconfigured => "Falsch konfiguriert".to_owned(),
+            MetaEvent::Approaching { percent, .. } => format!("{percent}% des Budgets verbraucht"),
+            MetaEvent::Sustained { windows, .. } => format!("{windows} Fenster in Folge voll"),
+            MetaEvent::Storm { call_sites, .. } => format!("Log-Sturm an {call_sites} Stellen"),
+            MetaEvent::IgnoredTicks { count, ticks, .. } => {
+                format!("{count} Logs in {ticks} Ticks ignoriert")
+            }
+            MetaEvent::Dumping { count, .. } => format!("{count} ignorierte Logs vor dem Fehler"),
+        });
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        }
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        clear_meta_formatter();
+
+        crate::testing_logger::validate(|captured_logs| {
+            let bodies: Vec<_> = captured_logs.iter().map(|log| log.body.as_str()).collect();
+            assert_eq!(bodies, ["Zu viele Logs!", "2 Logs in 2s ignoriert"]);
+        });
+    }
+
+    #[cfg(feature = "json-meta")]
+    #[test]
+    fn json_targets_are_escaped() {
+        let target = "it's \"caf\u{e9}\"\\\n\u{1}";
+        let event = super::Structured {
+            event: MetaEvent::Threshold,
+            target: Some(target),
+            dropped: 0,
+        };
+        let json: serde_json::Value = serde_json::from_str(&event.to_string()).unwrap();
+        assert_eq!(json["target"], target);
+    }
+
+    #[cfg(feature = "json-meta")]
+    #[test]
+    fn json_events_parse() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+        }
+        clock.advance(Duration::from_millis(1500));
+        rate_limiter.log_maybe(Duration::from_secs(1), 1, || {});
+
+        crate::testing_logger::validate(|captured_logs| {
+            let events: Vec<serde_json::Value> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with(r#"{"log_limit""#))
+                .map(|log| serde_json::from_str(&log.body).unwrap())
+                .collect();
+            assert!(events.contains(&serde_json::json!({"log_limit": "threshold"})));
+            assert!(events.contains(
+                &serde_json::json!({"log_limit": "ignored", "count": 2, "since_ms": 1500})
+            ));
+        });
+    }
+
+    #[cfg(all(feature = "logfmt-meta", not(feature = "json-meta")))]
+    #[test]
+    fn logfmt_events_carry_their_fields() {
+        crate::testing_logger::setup();
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let context = crate::LogContext {
+            level: log::Level::Info,
+            target: "logfmt_user",
+        };
+        for _ in 0..3 {
+            rate_limiter.log_maybe_with_context(Duration::from_secs(1), 1, &context, |_| {});
+        }
+        clock.advance(Duration::from_millis(3200));
+        rate_limiter.log_maybe_with_context(Duration::from_secs(1), 1, &context, |_| {});
+
+        crate::testing_logger::validate(|captured_logs| {
+            let summary = captured_logs
+                .iter()
+                .find(|log| log.body.starts_with("log_limit=ignored"))
+                .expect("no logfmt summary");
+            let pairs: Vec<_> = summary.body.split(' ').collect();
+            assert!(pairs.contains(&"count=2"));
+            assert!(pairs.contains(&"since=3.2s"));
+            assert!(pairs.contains(&"target=logfmt_user"));
+            assert!(captured_logs
+                .iter()
+                .any(|log| log.body == "log_limit=threshold"));
+        });
+    }
+
+    #[cfg(all(feature = "logfmt-meta", not(feature = "json-meta")))]
+    #[test]
+    fn logfmt_values_are_quoted_and_escaped() {
+        let value = |value| super::LogfmtValue(value).to_string();
+        assert_eq!(value("my_crate::net"), "my_crate::net");
+        assert_eq!(value(""), r#""""#);
+        assert_eq!(value("a b"), r#""a b""#);
+        assert_eq!(value("a=b"), r#""a=b""#);
+        assert_eq!(value(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(value(r"C:\temp"), r#""C:\\temp""#);
+        assert_eq!(value("a\nb\t\u{1}"), r#""a\nb\t\u0001""#);
+    }
+}
@@ -0,0 +1,182 @@
+//! Bridge from the limiters to an OpenTelemetry metrics and logs pipeline.
+//!
+//! Once [`init`] is called, every log emitted or suppressed by the `*_limit!` and
+//! `*_limit_global!` macros increments the `log_limit.emitted` or `log_limit.suppressed` counter.
+//! Once [`init_logs`] is called, the summary of each window's suppressed logs is also emitted as
+//! an OpenTelemetry log record. The emitted logs themselves still go through the `log` facade, so
+//! pair this with `opentelemetry-appender-log` to turn them into OpenTelemetry log records.
+
+use opentelemetry::logs::LogRecord;
+use opentelemetry::logs::Logger;
+use opentelemetry::logs::Severity;
+use opentelemetry::metrics::Counter;
+use opentelemetry::metrics::Meter;
+use std::fmt;
+use std::sync::OnceLock;
+
+struct Counters {
+    emitted: Counter<u64>,
+    suppressed: Counter<u64>,
+}
+
+static COUNTERS: OnceLock<Counters> = OnceLock::new();
+
+/// Creates the counters on `meter` and starts recording into them. Only the first call has an
+/// effect.
+pub fn init(meter: &Meter) {
+    COUNTERS.get_or_init(|| Counters {
+        emitted: meter
+            .u64_counter("log_limit.emitted")
+            .with_description("Logs emitted by the rate limiters")
+            .build(),
+        suppressed: meter
+            .u64_counter("log_limit.suppressed")
+            .with_description("Logs suppressed by the rate limiters")
+            .build(),
+    });
+}
+
+type SummaryLogger = Box<dyn Fn(usize, Option<&str>, &dyn fmt::Display) + Send + Sync>;
+
+static SUMMARY_LOGGER: OnceLock<SummaryLogger> = OnceLock::new();
+
+/// Starts emitting the summary of each window's suppressed logs on `logger`, as a warn record
+/// named `log_limit.ignored` with the summary as its body, the target of the suppressed logs as
+/// its target, if known, and their count as its `log_limit.suppressed` attribute. The summaries
+/// count against the meta-message ceiling and need the `warning-messages` feature like their
+/// `log` counterparts. Only the first call has an effect.
+pub fn init_logs<L: Logger + Send + Sync + 'static>(logger: L) {
+    let _ = SUMMARY_LOGGER.set(Box::new(move |count, target, summary| {
+        let mut record = logger.create_log_record();
+        record.set_event_name("log_limit.ignored");
+        record.set_severity_number(Severity::Warn);
+        record.set_severity_text("WARN");
+        record.set_body(summary.to_string().into());
+        if let Some(target) = target {
+            record.set_target(target.to_owned());
+        }
+        record.add_attribute(
+            "log_limit.suppressed",
+            i64::try_from(count).unwrap_or(i64::MAX),
+        );
+        logger.emit(record);
+    }));
+}
+
+#[cfg(feature = "warning-messages")]
+pub(crate) fn record_summary(count: usize, target: Option<&str>, summary: &dyn fmt::Display) {
+    if let Some(summary_logger) = SUMMARY_LOGGER.get() {
+        summary_logger(count, target, summary);
+    }
+}
+
+pub(crate) fn record_emitted() {
+    if let Some(counters) = COUNTERS.get() {
+        counters.emitted.add(1, &[]);
+    }
+}
+
+pub(crate) fn record_suppressed() {
+    if let Some(counters) = COUNTERS.get() {
+        counters.suppressed.add(1, &[]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::info_limit;
+    use crate::info_limit_global;
+    use opentelemetry::metrics::MeterProvider;
+    use opentelemetry_sdk::metrics::data::AggregatedMetrics;
+    use opentelemetry_sdk::metrics::data::MetricData;
+    use opentelemetry_sdk::metrics::InMemoryMetricExporter;
+    use opentelemetry_sdk::metrics::PeriodicReader;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use std::time::Duration;
+
+    fn counter_value(exporter: &InMemoryMetricExporter, name: &str) -> u64 {
+        let metrics = exporter.get_finished_metrics().unwrap();
+        let resource_metrics = metrics.last().unwrap();
+        let metric = resource_metrics
+            .scope_metrics()
+            .flat_map(|scope_metrics| scope_metrics.metrics())
+            .find(|metric| metric.name() == name)
+            .unwrap();
+        match metric.data() {
+            AggregatedMetrics::U64(MetricData::Sum(sum)) => {
+                sum.data_points().map(|data_point| data_point.value()).sum()
+            }
+            _ => panic!("{name} isn't a u64 counter"),
+        }
+    }
+
+    #[test]
+    fn counts_emitted_and_suppressed_logs() {
+        crate::testing_logger::setup();
+        let exporter = InMemoryMetricExporter::default();
+        let meter_provider = SdkMeterProvider::builder()
+            .with_reader(PeriodicReader::builder(exporter.clone()).build())
+            .build();
+        super::init(&meter_provider.meter("log_limit"));
+
+        for _ in 0..5 {
+            info_limit!(2, Duration::from_secs(60), "thread");
+            info_limit_global!(1, Duration::from_secs(60), "global");
+        }
+        meter_provider.force_flush().unwrap();
+
+        assert_eq!(counter_value(&exporter, "log_limit.emitted"), 3);
+        assert_eq!(counter_value(&exporter, "log_limit.suppressed"), 7);
+    }
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn summaries_are_emitted_as_log_records() {
+        use opentelemetry::logs::AnyValue;
+        use opentelemetry::logs::LoggerProvider;
+        use opentelemetry::logs::Severity;
+        use opentelemetry_sdk::logs::InMemoryLogExporter;
+        use opentelemetry_sdk::logs::SdkLoggerProvider;
+
+        crate::testing_logger::setup();
+        let exporter = InMemoryLogExporter::default();
+        let logger_provider = SdkLoggerProvider::builder()
+            .with_simple_exporter(exporter.clone())
+            .build();
+        super::init_logs(logger_provider.logger("log_limit"));
+        let clock = crate::ManualClock::new();
+        let mut rate_limiter = crate::RateLimiter::with_clock(clock.clone());
+        let context = crate::LogContext {
+            level: log::Level::Info,
+            target: "otel_summaries",
+        };
+        for _ in 0..5 {
+            rate_limiter.log_maybe_with_context(Duration::from_secs(1), 2, &context, |_| {});
+        }
+        clock.advance(Duration::from_secs(2));
+        rate_limiter.log_maybe_with_context(Duration::from_secs(1), 2, &context, |_| {});
+
+        let logs = exporter.get_emitted_logs().unwrap();
+        let summaries: Vec<_> = logs
+            .iter()
+            .map(|log| &log.record)
+            .filter(|record| {
+                record
+                    .target()
+                    .is_some_and(|target| target == "otel_summaries")
+            })
+            .collect();
+        assert_eq!(summaries.len(), 1);
+        let summary = summaries[0];
+        assert_eq!(summary.event_name(), Some("log_limit.ignored"));
+        assert_eq!(summary.severity_number(), Some(Severity::Warn));
+        assert!(summary.attributes_iter().any(|(key, value)| key.as_str()
+            == "log_limit.suppressed"
+            && *value == AnyValue::Int(3)));
+        #[cfg(not(any(feature = "json-meta", feature = "logfmt-meta")))]
+        assert!(matches!(
+            summary.body(),
+            Some(AnyValue::String(body)) if body.as_str().starts_with("Ignored 3 logs from otel_summaries")
+        ));
+    }
+}
@@ -0,0 +1,30 @@
+use crate::Instant;
+use std::cell::Cell;
+use std::time::Duration;
+
+/// Measures the time spent in a limiter call, excluding the time spent logging.
+pub(crate) struct Timer {
+    start: Instant,
+    excluded: Cell<Duration>,
+}
+
+impl Timer {
+    pub(crate) fn start() -> Self {
+        Self {
+            start: Instant::now(),
+            excluded: Cell::new(Duration::ZERO),
+        }
+    }
+
+    /// Runs `f` without counting its time.
+    pub(crate) fn exclude(&self, f: &dyn Fn()) {
+        let start = Instant::now();
+        f();
+        self.excluded.set(self.excluded.get() + start.elapsed());
+    }
+
+    pub(crate) fn elapsed_nanos(&self) -> u64 {
+        let elapsed = self.start.elapsed().saturating_sub(self.excluded.get());
+        u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX)
+    }
+}
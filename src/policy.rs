@@ -0,0 +1,180 @@
+//! Limits shared by every call site whose target matches a pattern.
+//!
+//! A pattern is either an exact target such as `my_crate::net` or a prefix ending in `*` such as
+//! `my_crate::*`, optionally restricted to a single level. All matching call sites draw from one
+//! shared budget, overriding the limits passed to the macros. When several policies match, the
+//! most specific wins: an exact target beats a wildcard, a longer wildcard prefix beats a shorter
+//! one and a level-specific policy beats one for all levels. This allows e.g. "all of `my_crate`:
+//! 5/sec, but errors in `my_crate`: 20/sec".
+
+use crate::LogContext;
+use crate::SynchronisedRateLimiter;
+use log::Level;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::RwLock;
+use std::time::Duration;
+
+struct Policy {
+    pattern: String,
+    level: Option<Level>,
+    max_per_time: usize,
+    period: Duration,
+    rate_limiter: Arc<SynchronisedRateLimiter>,
+}
+
+impl Policy {
+    /// How well the policy matches, if at all. Larger is more specific.
+    fn specificity(&self, target: &str, level: Level) -> Option<(bool, usize, bool)> {
+        if self.level.is_some_and(|policy_level| policy_level != level) {
+            return None;
+        }
+        let matched = match self.pattern.strip_suffix('*') {
+            Some(prefix) if target.starts_with(prefix) => (false, prefix.len()),
+            None if self.pattern == target => (true, target.len()),
+            _ => return None,
+        };
+        Some((matched.0, matched.1, self.level.is_some()))
+    }
+}
+
+static POLICIES: RwLock<Vec<Policy>> = RwLock::new(Vec::new());
+// Lets the macros skip taking the lock when no policies are in use.
+static ANY_POLICIES: AtomicBool = AtomicBool::new(false);
+
+/// Limits all call sites matching `pattern` (and `level`, if given) to a shared budget of
+/// `max_per_time` logs per `period`. Replaces any policy with the same pattern and level. Its
+/// limiter takes the [`LimiterConfig::approaching_percent`](crate::LimiterConfig::approaching_percent)
+/// set by [`set_macro_config`](crate::set_macro_config) at the time.
+pub fn set_limit(pattern: &str, level: Option<Level>, max_per_time: usize, period: Duration) {
+    let mut policies = POLICIES.write().unwrap();
+    policies.retain(|policy| policy.pattern != pattern || policy.level != level);
+    policies.push(Policy {
+        pattern: pattern.to_owned(),
+        level,
+        max_per_time,
+        period,
+        rate_limiter: Arc::new(crate::__private::macro_synchronised_rate_limiter()),
+    });
+    ANY_POLICIES.store(true, Ordering::Relaxed);
+}
+
+/// Removes all policies, returning every call site to the limits passed to its macro.
+pub fn clear_limits() {
+    POLICIES.write().unwrap().clear();
+    ANY_POLICIES.store(false, Ordering::Relaxed);
+}
+
+/// Logs through the budget of the most specific policy matching the context. Returns `false`
+/// without logging if there is no such policy.
+#[doc(hidden)]
+pub fn log_maybe(context: &LogContext, log: impl Fn()) -> bool {
+    if !ANY_POLICIES.load(Ordering::Relaxed) {
+        return false;
+    }
+    let policies = POLICIES.read().unwrap();
+    let Some(policy) = policies
+        .iter()
+        .filter_map(|policy| Some((policy.specificity(context.target, context.level)?, policy)))
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, policy)| policy)
+    else {
+        return false;
+    };
+    policy
+        .rate_limiter
+        .log_maybe(policy.period, policy.max_per_time, log);
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::clear_limits;
+    use super::log_maybe;
+    use super::set_limit;
+    use crate::info_limit;
+    use crate::info_limit_global;
+    use crate::LogContext;
+    use crate::ManualClock;
+    use crate::RateLimiter;
+    use log::Level;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    const PERIOD: Duration = Duration::from_secs(60);
+
+    fn emissions(target: &str, level: Level, calls: usize) -> Option<usize> {
+        let emitted = Cell::new(0);
+        let context = LogContext { level, target };
+        for _ in 0..calls {
+            if !log_maybe(&context, || emitted.set(emitted.get() + 1)) {
+                return None;
+            }
+        }
+        Some(emitted.get())
+    }
+
+    #[test]
+    fn wildcards_match_by_prefix() {
+        set_limit("my_crate::*", None, 5, PERIOD);
+        assert_eq!(emissions("my_crate::net", Level::Info, 10), Some(5));
+        assert_eq!(emissions("my_crate::disk::io", Level::Info, 10), Some(0));
+        assert_eq!(emissions("my_crate", Level::Info, 1), None);
+        assert_eq!(emissions("other_crate::net", Level::Info, 1), None);
+        clear_limits();
+        assert_eq!(emissions("my_crate::net", Level::Info, 1), None);
+    }
+
+    #[test]
+    fn most_specific_policy_wins() {
+        set_limit("*", None, 1, PERIOD);
+        set_limit("my_crate::*", None, 2, PERIOD);
+        set_limit("my_crate::*", Some(Level::Error), 3, PERIOD);
+        set_limit("my_crate::net::*", None, 4, PERIOD);
+        set_limit("my_crate::net::tcp", None, 5, PERIOD);
+
+        assert_eq!(emissions("other_crate", Level::Info, 10), Some(1));
+        assert_eq!(emissions("my_crate::disk", Level::Info, 10), Some(2));
+        assert_eq!(emissions("my_crate::disk", Level::Error, 10), Some(3));
+        assert_eq!(emissions("my_crate::net::udp", Level::Error, 10), Some(4));
+        assert_eq!(emissions("my_crate::net::tcp", Level::Error, 10), Some(5));
+        clear_limits();
+    }
+
+    #[test]
+    fn context_target_decides_which_budget_applies() {
+        set_limit("my_crate::net::*", None, 1, PERIOD);
+        let mut rate_limiter = RateLimiter::with_clock(ManualClock::new());
+        let emitted = Cell::new(0);
+        for target in ["my_crate::net::tcp", "my_crate::disk"] {
+            let context = LogContext {
+                level: Level::Info,
+                target,
+            };
+            for _ in 0..5 {
+                rate_limiter.log_maybe_with_context(PERIOD, 3, &context, |_| {
+                    emitted.set(emitted.get() + 1)
+                });
+            }
+        }
+        clear_limits();
+        // One through the policy for the network target, three through the limiter's own budget.
+        assert_eq!(emitted.get(), 4);
+    }
+
+    #[test]
+    fn macros_consult_policies() {
+        crate::testing_logger::setup();
+        set_limit("log_limit::policy::*", Some(Level::Info), 3, PERIOD);
+        for _ in 0..5 {
+            info_limit!(100, PERIOD, "first call site");
+            info_limit_global!(100, PERIOD, "second call site");
+        }
+        clear_limits();
+        crate::testing_logger::validate(|captured_logs| {
+            let info_logs = captured_logs.iter().filter(|log| log.level == Level::Info);
+            assert_eq!(info_logs.count(), 3);
+        });
+    }
+}
@@ -0,0 +1,209 @@
+use crate::LimiterSnapshot;
+use std::time::Duration;
+
+/// How to round a budget that doesn't divide evenly, see [`Rate::budget_for`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    /// Never exceed the requested rate.
+    Floor,
+    /// Never fall short of the requested rate.
+    Ceil,
+    /// Stay within half a log per window of the requested rate.
+    Nearest,
+}
+
+/// A rate such as "7 logs per 2 seconds", for deriving the `(max_per_time, period)` of a window
+/// of a different length.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rate {
+    pub(crate) count: usize,
+    pub(crate) per: Duration,
+}
+
+impl Rate {
+    /// # Panics
+    ///
+    /// If `per` is zero.
+    pub fn new(count: usize, per: Duration) -> Self {
+        assert!(!per.is_zero(), "A rate needs a non-zero period");
+        Self { count, per }
+    }
+
+    /// The budget of a `window` long window, rounded as given, returned as the
+    /// `(max_per_time, period)` the limiters take.
+    ///
+    /// The achieved rate is the budget divided by the window, which only matches the requested
+    /// rate when the budget divides evenly. Otherwise [`Rounding::Floor`] falls short of it and
+    /// [`Rounding::Ceil`] exceeds it, each by less than one log per window, while
+    /// [`Rounding::Nearest`] is off by at most half a log per window.
+    pub fn budget_for(&self, window: Duration, rounding: Rounding) -> (usize, Duration) {
+        // A product this large is a budget beyond `usize` anyway.
+        let Some(numerator) = (self.count as u128).checked_mul(window.as_nanos()) else {
+            return (usize::MAX, window);
+        };
+        let denominator = self.per.as_nanos();
+        let (quotient, remainder) = (numerator / denominator, numerator % denominator);
+        let max_per_time = match rounding {
+            Rounding::Floor => quotient,
+            Rounding::Ceil => quotient + u128::from(remainder > 0),
+            Rounding::Nearest => quotient + u128::from(remainder >= denominator - denominator / 2),
+        };
+        (usize::try_from(max_per_time).unwrap_or(usize::MAX), window)
+    }
+}
+
+/// Suggests the `(max_per_time, period)` that would have let through `fraction` of the logs
+/// recorded in `windows`, one snapshot taken at the end of each `period` long window, e.g. with
+/// [`RateLimiter::snapshot`](crate::RateLimiter::snapshot) just before it rolls over. Returns
+/// `None` if no logs were recorded.
+///
+/// # Panics
+///
+/// If `fraction` isn't within `0.0..=1.0`.
+pub fn suggest_limits(
+    windows: &[LimiterSnapshot],
+    period: Duration,
+    fraction: f64,
+) -> Option<(usize, Duration)> {
+    assert!(
+        (0.0..=1.0).contains(&fraction),
+        "The fraction of logs to allow must be within 0.0..=1.0"
+    );
+    let totals: Vec<_> = windows
+        .iter()
+        .map(|window| window.count.saturating_add(window.suppressed))
+        .collect();
+    let total = totals.iter().map(|&logs| logs as f64).sum::<f64>();
+    if total == 0.0 {
+        return None;
+    }
+    let allowed = |max_per_time: usize| {
+        totals
+            .iter()
+            .map(|&logs| logs.min(max_per_time) as f64)
+            .sum::<f64>()
+    };
+    // The smallest budget allowing enough logs, which always exists as the busiest window's
+    // total allows them all.
+    let (mut low, mut high) = (0, totals.iter().copied().max().unwrap_or(0));
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if allowed(mid) >= fraction * total {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Some((low, period))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::suggest_limits;
+    use super::Rate;
+    use super::Rounding;
+    use crate::Clock;
+    use crate::LimiterSnapshot;
+    use crate::ManualClock;
+    use crate::RateLimiter;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    /// Floods a limiter with the budget of one second windows for ten windows.
+    fn emitted_over_ten_windows(rate: Rate, rounding: Rounding) -> usize {
+        let (max_per_time, period) = rate.budget_for(Duration::from_secs(1), rounding);
+        let clock = ManualClock::new();
+        let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+        let emitted = Cell::new(0);
+        for _ in 0..10 {
+            for _ in 0..100 {
+                rate_limiter.log_maybe(period, max_per_time, || emitted.set(emitted.get() + 1));
+            }
+            clock.advance(period + Duration::from_millis(1));
+        }
+        emitted.get()
+    }
+
+    #[test]
+    fn rounding_policies() {
+        // 3.5 logs per second.
+        let rate = Rate::new(7, Duration::from_secs(2));
+        let one_second = Duration::from_secs(1);
+        assert_eq!(
+            rate.budget_for(one_second, Rounding::Floor),
+            (3, one_second)
+        );
+        assert_eq!(rate.budget_for(one_second, Rounding::Ceil), (4, one_second));
+        assert_eq!(
+            rate.budget_for(one_second, Rounding::Nearest),
+            (4, one_second)
+        );
+        assert_eq!(emitted_over_ten_windows(rate, Rounding::Floor), 30);
+        assert_eq!(emitted_over_ten_windows(rate, Rounding::Ceil), 40);
+        assert_eq!(emitted_over_ten_windows(rate, Rounding::Nearest), 40);
+
+        // 3.4 logs per second.
+        let rate = Rate::new(17, Duration::from_secs(5));
+        assert_eq!(emitted_over_ten_windows(rate, Rounding::Floor), 30);
+        assert_eq!(emitted_over_ten_windows(rate, Rounding::Ceil), 40);
+        assert_eq!(emitted_over_ten_windows(rate, Rounding::Nearest), 30);
+    }
+
+    #[test]
+    fn even_budgets_are_exact() {
+        let rate = Rate::new(12, Duration::from_secs(2));
+        for rounding in [Rounding::Floor, Rounding::Ceil, Rounding::Nearest] {
+            assert_eq!(
+                rate.budget_for(Duration::from_millis(500), rounding),
+                (3, Duration::from_millis(500))
+            );
+        }
+    }
+
+    #[test]
+    fn huge_budgets_saturate() {
+        let rate = Rate::new(usize::MAX, Duration::from_nanos(1));
+        for rounding in [Rounding::Floor, Rounding::Ceil, Rounding::Nearest] {
+            assert_eq!(
+                rate.budget_for(Duration::MAX, rounding),
+                (usize::MAX, Duration::MAX)
+            );
+        }
+        // Products close to the limit are still rounded exactly.
+        let rate = Rate::new(1 << 30, Duration::MAX);
+        let window = Duration::new(u64::MAX - 1, 999_999_999);
+        assert_eq!(
+            rate.budget_for(window, Rounding::Floor),
+            ((1 << 30) - 1, window)
+        );
+        assert_eq!(
+            rate.budget_for(window, Rounding::Nearest),
+            (1 << 30, window)
+        );
+    }
+
+    #[test]
+    fn suggestions_cover_the_requested_fraction() {
+        let window_start = ManualClock::new().now();
+        let windows: Vec<_> = [10, 12, 8, 11, 9, 10, 300, 10, 12, 9]
+            .into_iter()
+            .map(|logs| LimiterSnapshot {
+                count: logs.min(20),
+                suppressed: logs.saturating_sub(20),
+                window_start,
+            })
+            .collect();
+        let period = Duration::from_secs(1);
+
+        // The one burst dominates the traffic, so covering half of it still needs a large budget.
+        let (max_per_time, suggested_period) = suggest_limits(&windows, period, 0.5).unwrap();
+        assert_eq!(suggested_period, period);
+        assert!((100..300).contains(&max_per_time), "{max_per_time}");
+        assert_eq!(suggest_limits(&windows, period, 1.0), Some((300, period)));
+        assert_eq!(suggest_limits(&windows, period, 0.0), Some((0, period)));
+        // A more typical mix, where the suggestion lands just above the usual window.
+        let (max_per_time, _) = suggest_limits(&windows[..6], period, 0.95).unwrap();
+        assert!((10..=12).contains(&max_per_time), "{max_per_time}");
+        assert_eq!(suggest_limits(&[], period, 0.95), None);
+    }
+}
@@ -0,0 +1,662 @@
+//! Compile-time registration of every limiter created by the macros.
+//!
+//! Each macro call site links an entry into a static slice, so enumerating the limiters through
+//! [`all_limiters`] has no runtime registration cost. Only the call sites compiled into the binary
+//! are listed, whether or not they've been hit yet.
+//!
+//! The thread-local `*_limit!` macros give each thread its own limiter per call site, and only the
+//! current thread's is reachable from here. Everything in this module that looks at or acts on
+//! them therefore only sees the calling thread's limiters: polling, exporting or resetting from
+//! one thread leaves the limiters of all other threads as they are. Only the `*_limit_global!`
+//! macros' limiters are shared by all threads, so use those for call sites that need to be
+//! flushed or exported from elsewhere, e.g. a timer or metrics thread.
+
+use crate::LimiterSnapshot;
+use crate::RateLimiter;
+use crate::SynchronisedRateLimiter;
+use log::Level;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fmt;
+use std::fmt::Write;
+use std::sync::LazyLock;
+use std::thread::LocalKey;
+
+#[doc(hidden)]
+#[linkme::distributed_slice]
+pub static LIMITERS: [RegisteredLimiter];
+
+/// The limiter behind a macro call site.
+#[doc(hidden)]
+pub enum LimiterKind {
+    ThreadLocal(&'static LocalKey<RefCell<RateLimiter>>),
+    Global(&'static LazyLock<SynchronisedRateLimiter>),
+}
+
+/// A macro call site and its limiter.
+pub struct RegisteredLimiter {
+    pub file: &'static str,
+    pub line: u32,
+    pub level: Level,
+    #[doc(hidden)]
+    pub kind: LimiterKind,
+}
+
+impl RegisteredLimiter {
+    /// Whether the limit applies process-wide (`*_limit_global!`) rather than per thread.
+    pub fn is_global(&self) -> bool {
+        matches!(self.kind, LimiterKind::Global(_))
+    }
+
+    /// Captures the limiter's accounting. For thread-local limiters this is the current thread's
+    /// limiter, or `None` if it's in use further up the stack, e.g. when called from within a
+    /// logger.
+    pub fn snapshot(&self) -> Option<LimiterSnapshot> {
+        match self.kind {
+            LimiterKind::ThreadLocal(key) => key.with(|rate_limiter| {
+                let rate_limiter = rate_limiter.try_borrow().ok()?;
+                Some(rate_limiter.snapshot())
+            }),
+            LimiterKind::Global(rate_limiter) => Some(rate_limiter.snapshot()),
+        }
+    }
+
+    /// Calls deciding whether to log so far, see [`RateLimiter::total_calls`]. For thread-local
+    /// limiters this is the current thread's limiter, or `None` if it's in use further up the
+    /// stack.
+    pub fn total_calls(&self) -> Option<u64> {
+        match self.kind {
+            LimiterKind::ThreadLocal(key) => key.with(|rate_limiter| {
+                let rate_limiter = rate_limiter.try_borrow().ok()?;
+                Some(rate_limiter.total_calls())
+            }),
+            LimiterKind::Global(rate_limiter) => Some(rate_limiter.total_calls()),
+        }
+    }
+
+    /// Whether the limiter has run for a full window without ever using up its budget, see
+    /// [`RateLimiter::never_throttled`]. For thread-local limiters this is the current thread's
+    /// limiter, or `None` if it's in use further up the stack.
+    pub fn never_throttled(&self) -> Option<bool> {
+        match self.kind {
+            LimiterKind::ThreadLocal(key) => key.with(|rate_limiter| {
+                let rate_limiter = rate_limiter.try_borrow().ok()?;
+                Some(rate_limiter.never_throttled())
+            }),
+            LimiterKind::Global(rate_limiter) => Some(rate_limiter.never_throttled()),
+        }
+    }
+}
+
+/// All limiters created by the macros.
+pub fn all_limiters() -> &'static [RegisteredLimiter] {
+    &LIMITERS
+}
+
+/// Polls every limiter created by the macros, see [`RateLimiter::poll`]. For thread-local
+/// limiters this only polls the current thread's limiter, skipping any that's in use further up
+/// the stack, e.g. when called from within a logger. The summaries pending in other threads'
+/// limiters are only logged once those threads poll or log through the call site themselves.
+pub fn poll_all() {
+    for limiter in all_limiters() {
+        match limiter.kind {
+            LimiterKind::ThreadLocal(key) => key.with(|rate_limiter| {
+                if let Ok(mut rate_limiter) = rate_limiter.try_borrow_mut() {
+                    rate_limiter.poll()
+                }
+            }),
+            LimiterKind::Global(rate_limiter) => rate_limiter.poll(),
+        }
+    }
+}
+
+/// Renders the accounting of every limiter created by the macros in the Prometheus text
+/// exposition format, e.g. to serve from a metrics endpoint of your own. For thread-local limiters
+/// this is the current thread's limiter, skipping any that's in use further up the stack, so it
+/// may also be called from within a logger or a log hook. Served from a thread of its own, it
+/// therefore exports the thread-local call sites as unused, and only the `*_limit_global!` call
+/// sites' accounting is that of the whole process.
+///
+/// Each call site is labelled by its `file`, `line` and `level`. The emitted and suppressed logs
+/// are those of the current window, so they're gauges; the calls are counted over the limiter's
+/// lifetime.
+pub fn export_prometheus() -> String {
+    let mut samples = Vec::new();
+    for limiter in all_limiters() {
+        let labels = format!(
+            r#"file="{}",line="{}",level="{}""#,
+            escape_label_value(limiter.file),
+            limiter.line,
+            limiter.level.as_str().to_ascii_lowercase()
+        );
+        let (Some(snapshot), Some(total_calls)) = (limiter.snapshot(), limiter.total_calls())
+        else {
+            continue;
+        };
+        samples.push((labels, snapshot, total_calls));
+    }
+
+    let mut exposition = String::new();
+    write_metric(
+        &mut exposition,
+        "log_limit_window_emitted",
+        "gauge",
+        "Logs emitted in the current window of a rate limited call site.",
+        samples
+            .iter()
+            .map(|(labels, snapshot, _)| (labels, snapshot.count as u64)),
+    );
+    write_metric(
+        &mut exposition,
+        "log_limit_window_suppressed",
+        "gauge",
+        "Logs suppressed in the current window of a rate limited call site.",
+        samples
+            .iter()
+            .map(|(labels, snapshot, _)| (labels, snapshot.suppressed as u64)),
+    );
+    write_metric(
+        &mut exposition,
+        "log_limit_calls_total",
+        "counter",
+        "Calls of a rate limited call site.",
+        samples
+            .iter()
+            .map(|(labels, _, total_calls)| (labels, *total_calls)),
+    );
+    exposition
+}
+
+/// Writes a metric of the Prometheus `kind`, e.g. `gauge`, with a sample per set of labels.
+fn write_metric<'a>(
+    exposition: &mut String,
+    name: &str,
+    kind: &str,
+    help: &str,
+    samples: impl Iterator<Item = (&'a String, u64)>,
+) {
+    // Writing to a `String` can't fail.
+    let _ = writeln!(exposition, "# HELP {name} {help}");
+    let _ = writeln!(exposition, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        let _ = writeln!(exposition, "{name}{{{labels}}} {value}");
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', r#"\""#)
+        .replace('\n', r"\n")
+}
+
+/// The version of the encoding produced by [`encode_state`], changed whenever the layout is.
+const STATE_VERSION: u8 = 1;
+const GLOBAL_FLAG: u8 = 0x80;
+
+/// A limiter's state as encoded by [`encode_state`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedLimiter {
+    pub file: String,
+    pub line: u32,
+    pub level: Level,
+    pub is_global: bool,
+    /// Logs emitted in the window at the time of encoding.
+    pub count: u64,
+    /// Logs suppressed in the window at the time of encoding.
+    pub suppressed: u64,
+    /// Calls over the limiter's lifetime.
+    pub total_calls: u64,
+}
+
+/// Encodes the state of every limiter created by the macros into a compact, versioned blob, e.g.
+/// to include in crash dumps and see what was being throttled with [`decode_state`] later. For
+/// thread-local limiters this is the current thread's limiter, skipping any that's in use further
+/// up the stack, e.g. when called from a panic hook while logging. Other threads' thread-local
+/// limiters aren't included.
+///
+/// The blob is a version byte and the number of limiters, then per limiter its file, line, level
+/// and counts, with integers as LEB128 varints.
+pub fn encode_state() -> Vec<u8> {
+    let states: Vec<_> = all_limiters()
+        .iter()
+        .filter_map(|limiter| Some((limiter, limiter.snapshot()?, limiter.total_calls()?)))
+        .collect();
+
+    let mut blob = vec![STATE_VERSION];
+    write_varint(&mut blob, states.len() as u64);
+    for (limiter, snapshot, total_calls) in states {
+        write_varint(&mut blob, limiter.file.len() as u64);
+        blob.extend_from_slice(limiter.file.as_bytes());
+        write_varint(&mut blob, limiter.line.into());
+        let flags = if limiter.is_global() { GLOBAL_FLAG } else { 0 };
+        blob.push(limiter.level as u8 | flags);
+        write_varint(&mut blob, snapshot.count as u64);
+        write_varint(&mut blob, snapshot.suppressed as u64);
+        write_varint(&mut blob, total_calls);
+    }
+    blob
+}
+
+/// Decodes a blob produced by [`encode_state`], possibly by another build of the program.
+pub fn decode_state(blob: &[u8]) -> Result<Vec<DecodedLimiter>, DecodeStateError> {
+    let mut reader = blob;
+    let version = *reader.first().ok_or(DecodeStateError::Truncated)?;
+    reader = &reader[1..];
+    if version != STATE_VERSION {
+        return Err(DecodeStateError::UnsupportedVersion(version));
+    }
+    let len = read_varint(&mut reader)?;
+    let mut limiters = Vec::new();
+    for _ in 0..len {
+        let file_len =
+            usize::try_from(read_varint(&mut reader)?).map_err(|_| DecodeStateError::Invalid)?;
+        if reader.len() < file_len {
+            return Err(DecodeStateError::Truncated);
+        }
+        let (file, rest) = reader.split_at(file_len);
+        reader = rest;
+        let file = String::from_utf8(file.to_vec()).map_err(|_| DecodeStateError::Invalid)?;
+        let line =
+            u32::try_from(read_varint(&mut reader)?).map_err(|_| DecodeStateError::Invalid)?;
+        let (&flags, rest) = reader.split_first().ok_or(DecodeStateError::Truncated)?;
+        reader = rest;
+        let level = Level::iter()
+            .find(|level| *level as u8 == flags & !GLOBAL_FLAG)
+            .ok_or(DecodeStateError::Invalid)?;
+        limiters.push(DecodedLimiter {
+            file,
+            line,
+            level,
+            is_global: flags & GLOBAL_FLAG != 0,
+            count: read_varint(&mut reader)?,
+            suppressed: read_varint(&mut reader)?,
+            total_calls: read_varint(&mut reader)?,
+        });
+    }
+    if !reader.is_empty() {
+        return Err(DecodeStateError::Invalid);
+    }
+    Ok(limiters)
+}
+
+/// Why [`decode_state`] failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeStateError {
+    /// The blob is of a version this build can't decode.
+    UnsupportedVersion(u8),
+    /// The blob ends early.
+    Truncated,
+    /// The blob isn't an encoding of limiter states.
+    Invalid,
+}
+
+impl fmt::Display for DecodeStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "limiter state of version {version}, expected version {STATE_VERSION}"
+            ),
+            Self::Truncated => write!(f, "truncated limiter state"),
+            Self::Invalid => write!(f, "invalid limiter state"),
+        }
+    }
+}
+
+impl Error for DecodeStateError {}
+
+fn write_varint(blob: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        blob.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    blob.push(value as u8);
+}
+
+fn read_varint(reader: &mut &[u8]) -> Result<u64, DecodeStateError> {
+    let mut value = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = reader.split_first().ok_or(DecodeStateError::Truncated)?;
+        *reader = rest;
+        // The last byte only has room for the top bit.
+        if shift == 63 && byte > 1 {
+            return Err(DecodeStateError::Invalid);
+        }
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(DecodeStateError::Invalid)
+}
+
+/// Spawns a task on the current Tokio runtime calling [`poll_all`] every `interval`, so the
+/// summaries of call sites that went quiet after a flood are logged without a timer of your own.
+///
+/// As the task runs on a runtime thread, it only polls the global limiters and the thread-local
+/// limiters of whichever thread it runs on, which on a multi-threaded runtime may be a different
+/// worker on every tick. Only the `*_limit_global!` call sites are reliably flushed, so use those
+/// for call sites that may go quiet after a flood.
+///
+/// It runs until the runtime shuts down, or until it's stopped with
+/// [`JoinHandle::abort`](tokio::task::JoinHandle::abort); dropping the handle detaches it rather
+/// than stopping it.
+///
+/// # Panics
+///
+/// If called outside a Tokio runtime.
+#[cfg(feature = "tokio")]
+pub fn spawn_flusher(interval: std::time::Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            poll_all();
+        }
+    })
+}
+
+/// Resets every limiter created by the macros, so tests sharing a call site start clean. For
+/// thread-local limiters this only resets the current thread's limiter.
+#[cfg(feature = "test-support")]
+pub fn reset_all_limiters() {
+    for limiter in all_limiters() {
+        match limiter.kind {
+            LimiterKind::ThreadLocal(key) => key.with(|rate_limiter| {
+                if let Ok(mut rate_limiter) = rate_limiter.try_borrow_mut() {
+                    rate_limiter.reset()
+                }
+            }),
+            LimiterKind::Global(rate_limiter) => rate_limiter.reset(),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __register {
+    ($level:expr, $kind:expr) => {
+        #[$crate::__private::linkme::distributed_slice($crate::registry::LIMITERS)]
+        #[linkme(crate = $crate::__private::linkme)]
+        static REGISTERED_LIMITER: $crate::registry::RegisteredLimiter =
+            $crate::registry::RegisteredLimiter {
+                file: file!(),
+                line: line!(),
+                level: $level,
+                kind: $kind,
+            };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::all_limiters;
+    use crate::info_limit;
+    #[cfg(feature = "test-support")]
+    use crate::info_limit_global;
+    use crate::warn_limit;
+    use crate::warn_limit_global;
+    use std::sync::Barrier;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn macro_call_sites_are_enumerable() {
+        // Without a logger the call sites wouldn't use any budget.
+        crate::testing_logger::setup();
+        let line = line!() + 2;
+        for _ in 0..3 {
+            warn_limit!(1, Duration::from_secs(60), "Registered");
+            warn_limit_global!(2, Duration::from_secs(60), "Registered");
+        }
+
+        let registered: Vec<_> = all_limiters()
+            .iter()
+            .filter(|limiter| limiter.file == file!() && (line..=line + 1).contains(&limiter.line))
+            .collect();
+        assert_eq!(registered.len(), 2);
+        for limiter in registered {
+            assert_eq!(limiter.level, log::Level::Warn);
+            let snapshot = limiter.snapshot().unwrap();
+            let expected_count = if limiter.is_global() { 2 } else { 1 };
+            assert_eq!(snapshot.count, expected_count);
+            assert_eq!(snapshot.suppressed, 3 - expected_count);
+        }
+    }
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn limiters_in_use_are_skipped_rather_than_panicking() {
+        crate::testing_logger::setup();
+        let line = line!() + 6;
+        let own_limiter = || {
+            all_limiters()
+                .iter()
+                .find(|limiter| limiter.file == file!() && limiter.line == line)
+        };
+        info_limit!(1, Duration::from_secs(60), "In use: {:?}", {
+            let own_limiter = own_limiter().unwrap();
+            (
+                own_limiter.snapshot(),
+                own_limiter.total_calls(),
+                own_limiter.never_throttled(),
+            )
+        });
+        crate::testing_logger::validate(|captured_logs| {
+            assert!(captured_logs
+                .iter()
+                .any(|log| log.body == "In use: (None, None, None)"));
+        });
+        assert_eq!(own_limiter().unwrap().total_calls(), Some(1));
+    }
+
+    #[test]
+    fn limiters_are_exported_for_prometheus() {
+        crate::testing_logger::setup();
+        let line = line!() + 2;
+        for _ in 0..5 {
+            info_limit!(2, Duration::from_secs(60), "Exported");
+            warn_limit_global!(4, Duration::from_secs(60), "Exported");
+        }
+
+        let exposition = super::export_prometheus();
+        let labels = |offset, level| {
+            format!(
+                r#"file="{}",line="{}",level="{level}""#,
+                file!(),
+                line + offset
+            )
+        };
+        for expected in [
+            "# TYPE log_limit_window_emitted gauge".to_owned(),
+            "# TYPE log_limit_calls_total counter".to_owned(),
+            format!("log_limit_window_emitted{{{}}} 2", labels(0, "info")),
+            format!("log_limit_window_suppressed{{{}}} 3", labels(0, "info")),
+            format!("log_limit_calls_total{{{}}} 5", labels(0, "info")),
+            format!("log_limit_window_emitted{{{}}} 4", labels(1, "warn")),
+            format!("log_limit_window_suppressed{{{}}} 1", labels(1, "warn")),
+            format!("log_limit_calls_total{{{}}} 5", labels(1, "warn")),
+        ] {
+            assert!(
+                exposition.lines().any(|line| line == expected),
+                "{expected} missing from:\n{exposition}"
+            );
+        }
+    }
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn limiters_are_exported_from_within_a_log() {
+        crate::testing_logger::setup();
+        let line = line!() + 3;
+        warn_limit_global!(3, Duration::from_secs(60), "Exporting");
+        for _ in 0..2 {
+            info_limit!(1, Duration::from_secs(60), "{}", super::export_prometheus());
+        }
+        let global = format!(r#"file="{}",line="{}",level="warn""#, file!(), line - 2);
+        let own = format!(r#"file="{}",line="{line}",level="info""#, file!());
+        crate::testing_logger::validate(|captured_logs| {
+            let exposition = captured_logs
+                .iter()
+                .find(|log| log.body.contains(&global))
+                .unwrap();
+            // Only the limiter logging the exposition itself is missing from it.
+            assert!(!exposition.body.contains(&own), "{}", exposition.body);
+        });
+    }
+
+    #[test]
+    fn limiter_states_round_trip_through_the_encoding() {
+        crate::testing_logger::setup();
+        let line = line!() + 2;
+        for _ in 0..5 {
+            info_limit!(2, Duration::from_secs(60), "Encoded");
+            warn_limit_global!(4, Duration::from_secs(60), "Encoded");
+            crate::error_limit!(1, Duration::from_secs(60), "Encoded");
+        }
+
+        let blob = super::encode_state();
+        let decoded = super::decode_state(&blob).unwrap();
+        let mut ours: Vec<_> = decoded
+            .into_iter()
+            .filter(|limiter| limiter.file == file!() && (line..=line + 2).contains(&limiter.line))
+            .collect();
+        // The linker doesn't keep the call sites in order.
+        ours.sort_by_key(|limiter| limiter.line);
+        let ours: Vec<_> = ours
+            .into_iter()
+            .map(|limiter| {
+                let (count, suppressed) = (limiter.count, limiter.suppressed);
+                (
+                    limiter.level,
+                    limiter.is_global,
+                    count,
+                    suppressed,
+                    limiter.total_calls,
+                )
+            })
+            .collect();
+        assert_eq!(
+            ours,
+            [
+                (log::Level::Info, false, 2, 3, 5),
+                (log::Level::Warn, true, 4, 1, 5),
+                (log::Level::Error, false, 1, 4, 5),
+            ]
+        );
+
+        assert_eq!(
+            super::decode_state(&blob[..blob.len() - 1]),
+            Err(super::DecodeStateError::Truncated)
+        );
+        let mut future = blob;
+        future[0] = 2;
+        assert_eq!(
+            super::decode_state(&future),
+            Err(super::DecodeStateError::UnsupportedVersion(2))
+        );
+    }
+
+    #[cfg(all(feature = "tokio", feature = "warning-messages"))]
+    #[tokio::test]
+    async fn flusher_summarises_idle_call_sites() {
+        crate::testing_logger::setup();
+        for _ in 0..5 {
+            crate::info_limit_global!(1, Duration::from_millis(20), "Flushed call site");
+        }
+        let flusher = super::spawn_flusher(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        flusher.abort();
+
+        #[cfg(not(any(feature = "json-meta", feature = "logfmt-meta")))]
+        let summary = "Ignored 4 logs since";
+        #[cfg(feature = "json-meta")]
+        let summary = r#"{"log_limit":"ignored","count":4,"#;
+        #[cfg(all(feature = "logfmt-meta", not(feature = "json-meta")))]
+        let summary = "log_limit=ignored count=4 ";
+        crate::testing_logger::validate(|captured_logs| {
+            assert!(captured_logs
+                .iter()
+                .any(|log| log.body.starts_with(summary)));
+        });
+    }
+
+    #[test]
+    fn exports_only_reach_the_current_threads_limiters() {
+        crate::testing_logger::setup();
+        let line = line!() + 2;
+        for _ in 0..5 {
+            info_limit!(2, Duration::from_secs(60), "Exported from elsewhere");
+            warn_limit_global!(4, Duration::from_secs(60), "Exported from elsewhere");
+        }
+
+        let exposition = thread::spawn(super::export_prometheus).join().unwrap();
+        let labels = |offset, level| {
+            format!(
+                r#"file="{}",line="{}",level="{level}""#,
+                file!(),
+                line + offset
+            )
+        };
+        for expected in [
+            format!("log_limit_calls_total{{{}}} 0", labels(0, "info")),
+            format!("log_limit_calls_total{{{}}} 5", labels(1, "warn")),
+        ] {
+            assert!(
+                exposition.lines().any(|line| line == expected),
+                "{expected} missing from:\n{exposition}"
+            );
+        }
+    }
+
+    #[test]
+    fn polling_only_reaches_the_current_threads_limiters() {
+        crate::testing_logger::setup();
+        let barrier = &Barrier::new(2);
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                let line = line!() + 2;
+                for _ in 0..3 {
+                    info_limit!(1, Duration::from_millis(10), "Flooded on another thread");
+                }
+                let limiter = all_limiters().iter().find(|limiter| limiter.line == line);
+                let suppressed = || limiter.unwrap().snapshot().unwrap().suppressed;
+                barrier.wait();
+                // The main thread polls after the window is over.
+                barrier.wait();
+                assert_eq!(suppressed(), 2);
+                super::poll_all();
+                assert_eq!(suppressed(), 0);
+            });
+            barrier.wait();
+            thread::sleep(Duration::from_millis(20));
+            super::poll_all();
+            barrier.wait();
+        });
+    }
+
+    #[cfg(feature = "test-support")]
+    #[test]
+    fn reset_lets_call_sites_start_clean() {
+        fn sub_test() {
+            crate::testing_logger::setup();
+            for _ in 0..5 {
+                info_limit!(2, Duration::from_secs(60), "Shared call site");
+                info_limit_global!(2, Duration::from_secs(60), "Shared call site");
+            }
+            crate::testing_logger::validate(|captured_logs| {
+                let info_logs = captured_logs
+                    .iter()
+                    .filter(|log| log.level == log::Level::Info);
+                assert_eq!(info_logs.count(), 4);
+            });
+        }
+
+        sub_test();
+        super::reset_all_limiters();
+        sub_test();
+    }
+}
@@ -0,0 +1,89 @@
+use crate::Clock;
+use crate::Decision;
+use crate::LimiterConfig;
+use crate::ManualClock;
+use crate::RateLimiter;
+use std::time::Duration;
+
+/// A call recorded by [`RateLimiter::with_recording`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RecordedCall {
+    /// When the call was made, relative to the creation of the limiter.
+    pub at: Duration,
+    pub period: Duration,
+    pub max_per_time: usize,
+    pub decision: Decision,
+}
+
+/// The calls a limiter saw since [`RateLimiter::with_recording`], with the options it had at
+/// the start, to reproduce its decisions offline with [`Recording::replay`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recording {
+    pub(crate) config: LimiterConfig,
+    pub(crate) calls: Vec<RecordedCall>,
+}
+
+impl Recording {
+    pub(crate) fn new(config: LimiterConfig) -> Self {
+        Self {
+            config,
+            calls: Vec::new(),
+        }
+    }
+
+    pub fn calls(&self) -> &[RecordedCall] {
+        &self.calls
+    }
+
+    /// Replays the recorded calls through a fresh limiter with the recorded options, driven by
+    /// `clock`, returning its decision for each call. Without jitter or pauses these match the
+    /// recorded decisions, as the limiter is deterministic given its clock. Like the original
+    /// limiter, the replaying one logs its own warnings.
+    pub fn replay(&self, clock: ManualClock) -> Vec<Decision> {
+        let mut rate_limiter =
+            RateLimiter::with_clock_and_config(clock.clone(), self.config).with_recording();
+        let start = clock.now();
+        for call in &self.calls {
+            let elapsed = clock.now().duration_since(start);
+            clock.advance(call.at.saturating_sub(elapsed));
+            rate_limiter.log_maybe(call.period, call.max_per_time, || {});
+        }
+        rate_limiter
+            .take_recording()
+            .map(|recording| recording.calls.iter().map(|call| call.decision).collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Decision;
+    use crate::LimiterConfig;
+    use crate::ManualClock;
+    use crate::RateLimiter;
+    use std::time::Duration;
+
+    #[test]
+    fn replaying_a_recording_reproduces_its_decisions() {
+        let config = LimiterConfig {
+            skip_first: true,
+            ..LimiterConfig::default()
+        };
+        let clock = ManualClock::new();
+        let mut rate_limiter =
+            RateLimiter::with_clock_and_config(clock.clone(), config).with_recording();
+        for call in 0..300 {
+            rate_limiter.log_maybe(Duration::from_millis(2), 5, || {});
+            if call % 10 == 0 {
+                clock.advance(Duration::from_micros(500));
+            }
+        }
+        let recording = rate_limiter.take_recording().unwrap();
+        let recorded: Vec<_> = recording.calls().iter().map(|call| call.decision).collect();
+        assert_eq!(recorded.len(), 300);
+        assert!(recorded.contains(&Decision::Suppress) && recorded.contains(&Decision::Rollover));
+
+        assert_eq!(recording.replay(ManualClock::new()), recorded);
+        assert!(rate_limiter.take_recording().unwrap().calls().is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+use crate::jitter;
+use crate::Decision;
+use crate::Instant;
+
+/// A hook called with the time and outcome of a sampled call, see
+/// [`RateLimiter::on_decision_sampled`](crate::RateLimiter::on_decision_sampled).
+pub(crate) type DecisionHook = dyn Fn(Instant, Decision) + Send + Sync;
+
+/// Picks a pseudo-random `rate` of the calls to pass to its hook.
+pub(crate) struct DecisionSampler {
+    rate: f64,
+    state: u64,
+    hook: Box<DecisionHook>,
+}
+
+impl DecisionSampler {
+    /// # Panics
+    ///
+    /// If `rate` isn't between 0 and 1.
+    pub(crate) fn new(rate: f64, seed: u64, hook: Box<DecisionHook>) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&rate),
+            "The sampling rate needs to be between 0 and 1"
+        );
+        Self {
+            rate,
+            state: seed,
+            hook,
+        }
+    }
+
+    /// Whether to sample the current call.
+    pub(crate) fn sample(&mut self) -> bool {
+        // The top 53 bits, as many as an `f64` holds exactly, as a fraction of 1.
+        let fraction = (jitter::splitmix64(&mut self.state) >> 11) as f64 / (1u64 << 53) as f64;
+        fraction < self.rate
+    }
+
+    pub(crate) fn record(&self, now: Instant, decision: Decision) {
+        (self.hook)(now, decision);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::Clock;
+use crate::LimiterConfig;
+use crate::RateLimiter;
+use crate::SystemClock;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Everything configuring a [`SharedLimiter`], replaced as a whole by
+/// [`SharedLimiter::set_config`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SharedLimiterConfig {
+    pub max_per_time: usize,
+    pub period: Duration,
+    pub options: LimiterConfig,
+}
+
+impl SharedLimiterConfig {
+    pub fn new(max_per_time: usize, period: Duration) -> Self {
+        Self {
+            max_per_time,
+            period,
+            options: LimiterConfig::default(),
+        }
+    }
+}
+
+/// A [`RateLimiter`] that owns its configuration and can be shared between threads and
+/// reconfigured at runtime. The configuration lives behind the same lock as the accounting, so
+/// every log sees either the old or the new configuration as a whole, never a mix of both.
+pub struct SharedLimiter<C: Clock = SystemClock> {
+    state: Mutex<State<C>>,
+}
+
+struct State<C: Clock> {
+    rate_limiter: RateLimiter<C>,
+    /// The configuration as last set, without any boosts.
+    config: SharedLimiterConfig,
+    /// The `max_per_time` of every active boost, in no particular order.
+    boosts: Vec<usize>,
+}
+
+impl<C: Clock> State<C> {
+    /// The configuration with the highest active boost applied.
+    fn effective_config(&self) -> SharedLimiterConfig {
+        let boost = self.boosts.iter().copied().max().unwrap_or(0);
+        SharedLimiterConfig {
+            max_per_time: self.config.max_per_time.max(boost),
+            ..self.config
+        }
+    }
+}
+
+impl SharedLimiter {
+    pub fn new(config: SharedLimiterConfig) -> Self {
+        Self::with_clock(SystemClock, config)
+    }
+}
+
+impl<C: Clock> SharedLimiter<C> {
+    pub fn with_clock(clock: C, config: SharedLimiterConfig) -> Self {
+        let rate_limiter = RateLimiter::with_clock_and_config(clock, config.options);
+        Self {
+            state: Mutex::new(State {
+                rate_limiter,
+                config,
+                boosts: Vec::new(),
+            }),
+        }
+    }
+
+    /// The configuration in effect, i.e. with the highest active boost applied.
+    pub fn config(&self) -> SharedLimiterConfig {
+        self.state.lock().unwrap().effective_config()
+    }
+
+    /// Atomically replaces the whole configuration, keeping the accounting of the current window.
+    /// Active boosts still apply on top of it.
+    pub fn set_config(&self, config: SharedLimiterConfig) {
+        let mut state = self.state.lock().unwrap();
+        state.rate_limiter.set_config(config.options);
+        state.config = config;
+    }
+
+    /// Raises `max_per_time` to at least `max_per_time` until the returned guard is dropped, e.g.
+    /// to log verbosely around an operation during an incident. Boosts may overlap and end in any
+    /// order, and the highest active one applies.
+    pub fn boost(&self, max_per_time: usize) -> BoostGuard<'_, C> {
+        self.state.lock().unwrap().boosts.push(max_per_time);
+        BoostGuard {
+            limiter: self,
+            max_per_time,
+        }
+    }
+
+    pub fn log_maybe(&self, log: impl Fn()) {
+        let mut state = self.state.lock().unwrap();
+        let config = state.effective_config();
+        state
+            .rate_limiter
+            .log_maybe(config.period, config.max_per_time, log);
+    }
+}
+
+/// Ends a boost of a [`SharedLimiter`] when dropped, see [`SharedLimiter::boost`].
+#[must_use = "the boost ends as soon as the guard is dropped"]
+pub struct BoostGuard<'a, C: Clock = SystemClock> {
+    limiter: &'a SharedLimiter<C>,
+    max_per_time: usize,
+}
+
+impl<C: Clock> Drop for BoostGuard<'_, C> {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        if let Some(index) = state
+            .boosts
+            .iter()
+            .position(|&boost| boost == self.max_per_time)
+        {
+            state.boosts.swap_remove(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SharedLimiter;
+    use super::SharedLimiterConfig;
+    use crate::ManualClock;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn swapped_config_applies_to_following_logs() {
+        let config = SharedLimiterConfig::new(1, Duration::from_secs(60));
+        let limiter = SharedLimiter::with_clock(ManualClock::new(), config);
+        let emitted = std::cell::Cell::new(0);
+        let log = || emitted.set(emitted.get() + 1);
+        limiter.log_maybe(log);
+        limiter.log_maybe(log);
+        assert_eq!(emitted.get(), 1);
+
+        limiter.set_config(SharedLimiterConfig::new(3, Duration::from_secs(60)));
+        for _ in 0..5 {
+            limiter.log_maybe(log);
+        }
+        assert_eq!(emitted.get(), 3);
+    }
+
+    #[test]
+    fn boost_applies_until_the_guard_drops() {
+        let clock = ManualClock::new();
+        let period = Duration::from_secs(1);
+        let limiter = SharedLimiter::with_clock(clock.clone(), SharedLimiterConfig::new(2, period));
+        let emitted = std::cell::Cell::new(0);
+        let log_ten_times = || {
+            emitted.set(0);
+            for _ in 0..10 {
+                limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+            }
+            clock.advance(period * 2);
+            emitted.get()
+        };
+
+        {
+            let _boost = limiter.boost(5);
+            assert_eq!(limiter.config().max_per_time, 5);
+            assert_eq!(log_ten_times(), 5);
+        }
+        assert_eq!(limiter.config().max_per_time, 2);
+        assert_eq!(log_ten_times(), 2);
+        assert_eq!(log_ten_times(), 2);
+    }
+
+    #[test]
+    fn boosts_dropped_out_of_order_restore_the_base() {
+        let limiter = SharedLimiter::new(SharedLimiterConfig::new(5, Duration::from_secs(1)));
+        let first = limiter.boost(10);
+        let second = limiter.boost(20);
+        assert_eq!(limiter.config().max_per_time, 20);
+        drop(first);
+        assert_eq!(limiter.config().max_per_time, 20);
+        limiter.set_config(SharedLimiterConfig::new(3, Duration::from_secs(1)));
+        assert_eq!(limiter.config().max_per_time, 20);
+        drop(second);
+        // The config set during the boosts is kept rather than overwritten.
+        assert_eq!(limiter.config().max_per_time, 3);
+    }
+
+    #[test]
+    fn config_is_never_torn_while_swapping() {
+        let first = SharedLimiterConfig::new(1, Duration::from_secs(1));
+        let second = SharedLimiterConfig::new(2, Duration::from_secs(2));
+        let limiter = SharedLimiter::with_clock(ManualClock::new(), first);
+        let done = AtomicBool::new(false);
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while !done.load(Ordering::Relaxed) {
+                        limiter.log_maybe(|| {});
+                        let config = limiter.config();
+                        assert!(config == first || config == second, "torn: {config:?}");
+                    }
+                });
+            }
+            for i in 0..10_000 {
+                limiter.set_config(if i % 2 == 0 { second } else { first });
+            }
+            done.store(true, Ordering::Relaxed);
+        });
+    }
+}
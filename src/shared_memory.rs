@@ -0,0 +1,97 @@
+use std::io;
+use std::ptr;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use std::time::SystemTime;
+
+#[repr(C)]
+struct State {
+    count: AtomicU64,
+    /// Nanoseconds since the Unix epoch, as processes don't share an [`std::time::Instant`].
+    window_start: AtomicU64,
+}
+
+/// A fixed window limiter whose state lives in a shared memory mapping, giving every process
+/// forked after its creation, e.g. the workers of a pre-fork server, a single combined budget.
+///
+/// Windows are timed by the system clock, since processes can't compare their [`Instant`]s, and
+/// roll over eagerly once `period` has elapsed. Calls racing with a rollover may be counted in
+/// either window.
+///
+/// [`Instant`]: std::time::Instant
+pub struct SharedMemoryLimiter {
+    state: *mut State,
+}
+
+// The state is only accessed through atomics.
+unsafe impl Send for SharedMemoryLimiter {}
+unsafe impl Sync for SharedMemoryLimiter {}
+
+impl SharedMemoryLimiter {
+    /// Maps the shared state, which is inherited by processes forked afterwards.
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: An anonymous mapping doesn't alias any existing memory.
+        let mapping = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                size_of::<State>(),
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if mapping == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let state = mapping.cast::<State>();
+        // SAFETY: The mapping is page aligned and large enough to hold the state.
+        unsafe {
+            state.write(State {
+                count: AtomicU64::new(0),
+                window_start: AtomicU64::new(unix_nanos()),
+            });
+        }
+        Ok(Self { state })
+    }
+
+    pub fn log_maybe(&self, period: Duration, max_per_time: usize, log: impl Fn()) {
+        // SAFETY: The state is initialised in `new` and stays mapped until `drop`.
+        let state = unsafe { &*self.state };
+        let now = unix_nanos();
+        let period = u64::try_from(period.as_nanos()).unwrap_or(u64::MAX);
+
+        let window_start = state.window_start.load(Ordering::Relaxed);
+        if now.saturating_sub(window_start) > period
+            && state
+                .window_start
+                .compare_exchange(window_start, now, Ordering::Relaxed, Ordering::Relaxed)
+                .is_ok()
+        {
+            state.count.store(0, Ordering::Relaxed);
+        }
+
+        let count = state.count.fetch_add(1, Ordering::Relaxed);
+        if count < u64::try_from(max_per_time).unwrap_or(u64::MAX) {
+            log();
+        }
+    }
+}
+
+impl Drop for SharedMemoryLimiter {
+    fn drop(&mut self) {
+        // SAFETY: The mapping was created in `new` with this size, and other processes keep their
+        // own mappings of it.
+        unsafe {
+            libc::munmap(self.state.cast(), size_of::<State>());
+        }
+    }
+}
+
+fn unix_nanos() -> u64 {
+    let since_epoch = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    u64::try_from(since_epoch.as_nanos()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,68 @@
+use crate::Clock;
+use crate::KeyedRateLimiter;
+use crate::SystemClock;
+use std::time::Duration;
+use tracing::span::Id;
+
+/// A separate budget per [`tracing`] span, e.g. per request, rather than per call site. Logs
+/// within the same span share a budget while each new span starts with a fresh one, bounding the
+/// volume a single request can log. Logs outside of any span, or while no subscriber is
+/// recording spans, share one budget.
+///
+/// At most `max_spans` spans are tracked, evicting the least recently used as in
+/// [`KeyedRateLimiter`], so closed spans are evicted in time. Note that subscribers may reuse the
+/// id of a closed span, in which case the new span picks up where the closed one left off.
+pub struct SpanLimiter<C: Clock + Clone = SystemClock> {
+    limiters: KeyedRateLimiter<Option<Id>, C>,
+}
+
+impl SpanLimiter {
+    pub fn new(max_spans: usize) -> Self {
+        Self::with_clock(SystemClock, max_spans)
+    }
+}
+
+impl<C: Clock + Clone> SpanLimiter<C> {
+    pub fn with_clock(clock: C, max_spans: usize) -> Self {
+        Self {
+            limiters: KeyedRateLimiter::with_clock(clock, max_spans),
+        }
+    }
+
+    /// Limits `log` against the budget of the current span.
+    pub fn log_maybe(&mut self, period: Duration, max_per_time: usize, log: impl Fn()) {
+        let span = tracing::Span::current().id();
+        self.limiters.log_maybe(span, period, max_per_time, log);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SpanLimiter;
+    use crate::ManualClock;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    #[test]
+    fn spans_have_independent_budgets() {
+        let _subscriber = tracing::subscriber::set_default(tracing_subscriber::registry());
+        let mut limiter = SpanLimiter::with_clock(ManualClock::new(), 10);
+        let period = Duration::from_secs(60);
+        let emitted = Cell::new(0);
+        let spans = [
+            tracing::info_span!("request", id = 1),
+            tracing::info_span!("request", id = 2),
+        ];
+        let mut per_span = Vec::new();
+        // Interleaved, so neither span's budget is used up before the other is entered.
+        for round in 0..6 {
+            let _entered = spans[round % 2].enter();
+            emitted.set(0);
+            for _ in 0..2 {
+                limiter.log_maybe(period, 3, || emitted.set(emitted.get() + 1));
+            }
+            per_span.push(emitted.get());
+        }
+        assert_eq!(per_span, [2, 2, 1, 1, 0, 0]);
+    }
+}
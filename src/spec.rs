@@ -0,0 +1,274 @@
+use crate::Rate;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+/// Limits written as `<max_per_time>/<period>`, e.g. `10/1s`, `100/1m` or `5/500ms`, for taking
+/// them from the command line or the environment. The period's unit is one of `ms`, `s`, `m` or
+/// `h`, and its number may be left out, as in `10/s`. Parsed with [`str::parse`], so it also
+/// works as e.g. a clap `value_parser`.
+///
+/// ```
+/// use log_limit::LimitSpec;
+/// use std::time::Duration;
+///
+/// let spec: LimitSpec = "100/1m".parse().unwrap();
+/// assert_eq!((spec.max_per_time, spec.period), (100, Duration::from_secs(60)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitSpec {
+    pub max_per_time: usize,
+    pub period: Duration,
+}
+
+impl From<LimitSpec> for Rate {
+    fn from(spec: LimitSpec) -> Self {
+        Rate::new(spec.max_per_time, spec.period)
+    }
+}
+
+/// Why a [`LimitSpec`] couldn't be parsed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseLimitSpecError {
+    spec: String,
+    kind: ErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ErrorKind {
+    MissingSlash,
+    InvalidCount(String),
+    InvalidPeriod(String),
+    ZeroPeriod,
+}
+
+impl fmt::Display for ParseLimitSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid limit spec {:?}: ", self.spec)?;
+        match &self.kind {
+            ErrorKind::MissingSlash => write!(f, "expected <count>/<period>, e.g. \"10/1s\""),
+            ErrorKind::InvalidCount(count) => write!(f, "{count:?} is not a count of logs"),
+            ErrorKind::InvalidPeriod(period) => {
+                write!(f, "{period:?} is not a period, {PERIOD_EXAMPLES}")
+            }
+            ErrorKind::ZeroPeriod => write!(f, "the period must be non-zero"),
+        }
+    }
+}
+
+impl Error for ParseLimitSpecError {}
+
+impl FromStr for LimitSpec {
+    type Err = ParseLimitSpecError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let error = |kind| ParseLimitSpecError {
+            spec: spec.to_owned(),
+            kind,
+        };
+        let (count, period) = spec
+            .split_once('/')
+            .ok_or_else(|| error(ErrorKind::MissingSlash))?;
+        let (count, period) = (count.trim(), period.trim());
+        let max_per_time = count
+            .parse()
+            .map_err(|_| error(ErrorKind::InvalidCount(count.to_owned())))?;
+        let period = parse_period(period)
+            .ok_or_else(|| error(ErrorKind::InvalidPeriod(period.to_owned())))?;
+        if period.is_zero() {
+            return Err(error(ErrorKind::ZeroPeriod));
+        }
+        Ok(Self {
+            max_per_time,
+            period,
+        })
+    }
+}
+
+const PERIOD_EXAMPLES: &str = r#"expected e.g. "500ms", "1s", "5m" or "1h""#;
+
+/// A period for the macros, which take either a [`Duration`] or a string in the format of a
+/// [`LimitSpec`]'s period, such as `"30s"`.
+#[doc(hidden)]
+pub trait IntoPeriod {
+    /// The period, parsing a string only once per call site into `parsed`.
+    fn into_period(self, parsed: &OnceLock<Duration>) -> Duration;
+}
+
+impl IntoPeriod for Duration {
+    #[inline]
+    fn into_period(self, _parsed: &OnceLock<Duration>) -> Duration {
+        self
+    }
+}
+
+impl IntoPeriod for &str {
+    /// # Panics
+    ///
+    /// If the string isn't a period.
+    #[track_caller]
+    fn into_period(self, parsed: &OnceLock<Duration>) -> Duration {
+        *parsed.get_or_init(|| match parse_period(self) {
+            Some(period) => period,
+            None => panic!("{self:?} is not a period, {PERIOD_EXAMPLES}"),
+        })
+    }
+}
+
+#[doc(hidden)]
+#[inline]
+#[track_caller]
+pub fn into_period(parsed: &OnceLock<Duration>, period: impl IntoPeriod) -> Duration {
+    period.into_period(parsed)
+}
+
+/// The period of a call site's macro, parsing a string period only on its first call.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __period {
+    ($period:expr) => {{
+        static PARSED: std::sync::OnceLock<std::time::Duration> = std::sync::OnceLock::new();
+        $crate::__private::into_period(&PARSED, $period)
+    }};
+}
+
+/// A period such as `"30s"`, `"500ms"`, `"5m"` or `"1h"` as a [`Duration`], checked at compile
+/// time, e.g. `info_limit!(10, period!("30s"), "Retrying")`.
+///
+/// ```compile_fail
+/// let period = log_limit::period!("30 seconds");
+/// ```
+#[macro_export]
+macro_rules! period {
+    ($period:literal) => {
+        const {
+            match $crate::__private::parse_period($period) {
+                Some(period) => period,
+                None => panic!(concat!(
+                    stringify!($period),
+                    r#" is not a period, expected e.g. "500ms", "1s", "5m" or "1h""#
+                )),
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+pub const fn parse_period(period: &str) -> Option<Duration> {
+    let bytes = period.as_bytes();
+    let mut unit_start = 0;
+    let mut number: u32 = 0;
+    while unit_start < bytes.len() && bytes[unit_start].is_ascii_digit() {
+        let digit = (bytes[unit_start] - b'0') as u32;
+        number = match number.checked_mul(10) {
+            Some(number) => match number.checked_add(digit) {
+                Some(number) => number,
+                None => return None,
+            },
+            None => return None,
+        };
+        unit_start += 1;
+    }
+    if unit_start == 0 {
+        number = 1;
+    }
+    let unit = match bytes.split_at(unit_start).1 {
+        b"ms" => Duration::from_millis(1),
+        b"s" => Duration::from_secs(1),
+        b"m" => Duration::from_secs(60),
+        b"h" => Duration::from_secs(60 * 60),
+        _ => return None,
+    };
+    unit.checked_mul(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LimitSpec;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+
+    fn into_period(period: impl super::IntoPeriod) -> Duration {
+        super::into_period(&OnceLock::new(), period)
+    }
+
+    fn parse(spec: &str) -> Result<(usize, Duration), String> {
+        spec.parse::<LimitSpec>()
+            .map(|spec| (spec.max_per_time, spec.period))
+            .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn valid_specs_parse() {
+        assert_eq!(parse("10/1s"), Ok((10, Duration::from_secs(1))));
+        assert_eq!(parse("100/1m"), Ok((100, Duration::from_secs(60))));
+        assert_eq!(parse("5/500ms"), Ok((5, Duration::from_millis(500))));
+        assert_eq!(parse("0/2h"), Ok((0, Duration::from_secs(7200))));
+        assert_eq!(parse(" 10 / s "), Ok((10, Duration::from_secs(1))));
+    }
+
+    #[test]
+    fn macro_periods_take_durations_and_strings() {
+        assert_eq!(into_period(Duration::from_secs(3)), Duration::from_secs(3));
+        assert_eq!(into_period("30s"), Duration::from_secs(30));
+        assert_eq!(into_period("500ms"), Duration::from_millis(500));
+        assert_eq!(into_period("5m"), Duration::from_secs(300));
+        assert_eq!(into_period("1h"), Duration::from_secs(3600));
+        assert_eq!(into_period("s"), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn string_periods_are_parsed_once_per_call_site() {
+        let parsed = OnceLock::new();
+        assert_eq!(super::into_period(&parsed, "30s"), Duration::from_secs(30));
+        assert_eq!(super::into_period(&parsed, "5m"), Duration::from_secs(30));
+        assert_eq!(
+            super::into_period(&parsed, Duration::from_secs(2)),
+            Duration::from_secs(2)
+        );
+    }
+
+    #[test]
+    fn literal_periods_are_checked_at_compile_time() {
+        const PERIOD: Duration = crate::period!("30s");
+        assert_eq!(PERIOD, Duration::from_secs(30));
+        assert_eq!(crate::period!("500ms"), Duration::from_millis(500));
+        assert_eq!(crate::period!("h"), Duration::from_secs(3600));
+    }
+
+    #[test]
+    #[should_panic(expected = r#""30 seconds" is not a period"#)]
+    fn macro_periods_panic_on_invalid_strings() {
+        into_period("30 seconds");
+    }
+
+    #[test]
+    fn invalid_specs_explain_themselves() {
+        assert_eq!(
+            parse("10"),
+            Err(r#"invalid limit spec "10": expected <count>/<period>, e.g. "10/1s""#.to_owned())
+        );
+        assert_eq!(
+            parse("ten/1s"),
+            Err(r#"invalid limit spec "ten/1s": "ten" is not a count of logs"#.to_owned())
+        );
+        assert_eq!(
+            parse("-1/1s"),
+            Err(r#"invalid limit spec "-1/1s": "-1" is not a count of logs"#.to_owned())
+        );
+        assert_eq!(
+            parse("10/1d"),
+            Err(r#"invalid limit spec "10/1d": "1d" is not a period, expected e.g. "500ms", "1s", "5m" or "1h""#.to_owned())
+        );
+        assert_eq!(
+            parse("10/1"),
+            Err(r#"invalid limit spec "10/1": "1" is not a period, expected e.g. "500ms", "1s", "5m" or "1h""#.to_owned())
+        );
+        assert_eq!(
+            parse("10/0s"),
+            Err(r#"invalid limit spec "10/0s": the period must be non-zero"#.to_owned())
+        );
+    }
+}
@@ -0,0 +1,150 @@
+//! Detects log storms spanning several call sites, e.g. one failure making many different log
+//! lines fire at once, and coalesces them into a single notice.
+//!
+//! Detection is off until [`set_detector`] is called. Every log emitted through the macros' call
+//! sites, as listed by the [registry](crate::registry), then counts towards a shared rate. Once
+//! that rate exceeds the threshold, a single notice names the call sites involved and every call
+//! site's budget is cut for a cooldown, instead of each site being throttled on its own. Only the
+//! thread-local and global macros feed it.
+
+use crate::Instant;
+use crate::Rate;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+struct Detector {
+    threshold: Rate,
+    cooldown: Duration,
+    budget_percent: u8,
+    window_start: Option<Instant>,
+    emitted: usize,
+    call_sites: Vec<(&'static str, u32)>,
+    storm_until: Option<Instant>,
+}
+
+static DETECTOR: Mutex<Option<Detector>> = Mutex::new(None);
+// Lets the macros skip taking the lock when detection is off.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+// Lets the macros skip taking the lock for their budget outside of storms.
+static STORMING: AtomicBool = AtomicBool::new(false);
+
+/// Declares a storm once more than `threshold` logs are emitted across all call sites, and then
+/// cuts every call site's budget to `budget_percent` of its own, but at least 1, for `cooldown`.
+/// Replaces any previous detector.
+pub fn set_detector(threshold: Rate, cooldown: Duration, budget_percent: u8) {
+    *DETECTOR.lock().unwrap() = Some(Detector {
+        threshold,
+        cooldown,
+        budget_percent,
+        window_start: None,
+        emitted: 0,
+        call_sites: Vec::new(),
+        storm_until: None,
+    });
+    STORMING.store(false, Ordering::Relaxed);
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stops detecting storms, restoring the call sites' budgets if one is ongoing.
+pub fn clear_detector() {
+    *DETECTOR.lock().unwrap() = None;
+    ENABLED.store(false, Ordering::Relaxed);
+    STORMING.store(false, Ordering::Relaxed);
+}
+
+/// Whether a storm is ongoing, i.e. the call sites' budgets are cut.
+pub fn is_storming() -> bool {
+    cut_percent().is_some()
+}
+
+/// Counts a log emitted by the call site at `file` and `line` towards the shared rate.
+#[doc(hidden)]
+pub fn on_emit(file: &'static str, line: u32) {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut guard = DETECTOR.lock().unwrap();
+    let Some(detector) = guard.as_mut() else {
+        return;
+    };
+    let now = Instant::now();
+    if detector
+        .storm_until
+        .is_some_and(|storm_until| now < storm_until)
+    {
+        return;
+    }
+    if detector
+        .window_start
+        .is_none_or(|window_start| now.duration_since(window_start) > detector.threshold.per)
+    {
+        detector.window_start = Some(now);
+        detector.emitted = 0;
+        detector.call_sites.clear();
+    }
+    detector.emitted += 1;
+    if !detector.call_sites.contains(&(file, line)) {
+        detector.call_sites.push((file, line));
+    }
+    if detector.emitted <= detector.threshold.count {
+        return;
+    }
+
+    detector.storm_until = Some(now + detector.cooldown);
+    detector.window_start = None;
+    STORMING.store(true, Ordering::Relaxed);
+    #[cfg(feature = "warning-messages")]
+    {
+        let call_sites: Vec<_> = detector
+            .call_sites
+            .iter()
+            .map(|(file, line)| format!("{file}:{line}"))
+            .collect();
+        let (emitted, per) = (detector.emitted, detector.threshold.per);
+        let (budget_percent, cooldown) = (detector.budget_percent, detector.cooldown);
+        // The notice may well be logged through a rate-limited call site again.
+        drop(guard);
+        crate::meta::warn_storm(
+            call_sites.len(),
+            format_args!(
+                "Log storm: {emitted} logs within {} from {} call sites. Cutting every budget to \
+                 {budget_percent}% for {}: {}",
+                crate::meta::FriendlyDuration(per),
+                call_sites.len(),
+                crate::meta::FriendlyDuration(cooldown),
+                call_sites.join(", ")
+            ),
+        );
+    }
+}
+
+/// The budget a call site with `max_per_time` gets, cut during storms.
+#[doc(hidden)]
+pub fn budget(max_per_time: usize) -> usize {
+    match cut_percent() {
+        Some(percent) => {
+            let cut = max_per_time.saturating_mul(usize::from(percent)) / 100;
+            cut.max(1).min(max_per_time)
+        }
+        None => max_per_time,
+    }
+}
+
+/// The percentage the budgets are cut to if a storm is ongoing, ending it once the cooldown's up.
+fn cut_percent() -> Option<u8> {
+    if !STORMING.load(Ordering::Relaxed) {
+        return None;
+    }
+    let mut detector = DETECTOR.lock().unwrap();
+    let detector = detector.as_mut()?;
+    match detector.storm_until {
+        Some(storm_until) if Instant::now() < storm_until => Some(detector.budget_percent),
+        _ => {
+            detector.storm_until = None;
+            STORMING.store(false, Ordering::Relaxed);
+            None
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+const NONE: u8 = 0;
+const FAILURE: u8 = 1;
+const SUCCESS: u8 = 2;
+
+/// Logs on changes of outcome only: the first failure of a streak of failures and the success
+/// ending it, but none of the repeats in between, without any notion of time. Suits retry loops
+/// and flapping status checks. The very first call always logs.
+pub struct StreakLimiter {
+    last: AtomicU8,
+    streak: AtomicUsize,
+}
+
+impl Default for StreakLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreakLimiter {
+    pub const fn new() -> Self {
+        Self {
+            last: AtomicU8::new(NONE),
+            streak: AtomicUsize::new(0),
+        }
+    }
+
+    /// The number of calls in a row with the same outcome, including the last one.
+    pub fn streak(&self) -> usize {
+        self.streak.load(Ordering::Relaxed)
+    }
+
+    pub fn log_maybe(&self, success: bool, log: impl Fn()) {
+        let outcome = if success { SUCCESS } else { FAILURE };
+        if self.last.swap(outcome, Ordering::Relaxed) == outcome {
+            self.streak.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.streak.store(1, Ordering::Relaxed);
+            log();
+        }
+    }
+}
+
+/// Logs at `$level` whenever `$success` differs from the previous call's, i.e. on the first
+/// failure after a success and vice versa. The streak applies to the entire process.
+///
+/// ```
+/// # let result: Result<(), &str> = Err("timed out");
+/// log_limit::log_streak!(result.is_ok(), log::Level::Warn, "Connection: {result:?}");
+/// ```
+#[macro_export]
+macro_rules! log_streak {
+    ($success:expr, $level:expr, $($arg:tt)+) => {{
+        static RATE_LIMITER: $crate::StreakLimiter = $crate::StreakLimiter::new();
+        RATE_LIMITER.log_maybe($success, || log::log!($level, $($arg)+));
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StreakLimiter;
+    use std::cell::RefCell;
+
+    #[test]
+    fn only_streak_transitions_are_emitted() {
+        let limiter = StreakLimiter::new();
+        let emitted = RefCell::new(Vec::new());
+        let outcomes = std::iter::repeat_n(false, 10).chain([true]);
+        for success in outcomes {
+            limiter.log_maybe(success, || emitted.borrow_mut().push(success));
+        }
+        assert_eq!(*emitted.borrow(), [false, true]);
+        assert_eq!(limiter.streak(), 1);
+    }
+
+    #[cfg(feature = "warning-messages")]
+    #[test]
+    fn macro_emits_on_transitions() {
+        crate::testing_logger::setup();
+        for attempt in 0..5 {
+            let success = attempt >= 3;
+            log_streak!(success, log::Level::Info, "Streak transition: {success}");
+        }
+        crate::testing_logger::validate(|captured_logs| {
+            let bodies: Vec<_> = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("Streak transition"))
+                .map(|log| log.body.as_str())
+                .collect();
+            assert_eq!(
+                bodies,
+                ["Streak transition: false", "Streak transition: true"]
+            );
+        });
+    }
+}
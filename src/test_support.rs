@@ -0,0 +1,102 @@
+//! Helpers for asserting on the behaviour of the limiters in downstream tests, from the logs
+//! captured by a test logger.
+
+/// The captured logs split into emitted logs and the limiters' own messages, see [`drop_stats`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DropStats {
+    /// The bodies of the logs that were let through.
+    pub emitted: Vec<String>,
+    /// The bodies of the limiters' own messages, i.e. the threshold warnings and summaries.
+    pub meta: Vec<String>,
+    /// The number of ignored logs reported by the summaries, in total.
+    pub ignored: usize,
+}
+
+/// Splits captured `(target, body)` pairs into emitted logs and the limiters' own messages, which
+/// are logged with the `log_limit` target, adding up the ignored counts of the summaries.
+pub fn drop_stats<'a>(logs: impl IntoIterator<Item = (&'a str, &'a str)>) -> DropStats {
+    let mut stats = DropStats::default();
+    for (target, body) in logs {
+        if target == "log_limit" {
+            stats.ignored += parse_ignored_count(body).unwrap_or(0);
+            stats.meta.push(body.to_owned());
+        } else {
+            stats.emitted.push(body.to_owned());
+        }
+    }
+    stats
+}
+
+/// The number of ignored logs reported by a summary, or `None` if `message` isn't one. Handles
+/// the default wording, including a [context](crate::RateLimiter::set_context) prefix, as well as
+/// the `json-meta` and `logfmt-meta` formats.
+pub fn parse_ignored_count(message: &str) -> Option<usize> {
+    let counted = if message.starts_with(r#"{"log_limit":"ignored""#) {
+        message.split_once(r#""count":"#)?.1
+    } else if message.starts_with("log_limit=ignored ") {
+        message.split_once(" count=")?.1
+    } else {
+        message.split_once("Ignored ")?.1
+    };
+    let digits = counted
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(counted.len());
+    counted[..digits].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_ignored_count;
+
+    #[test]
+    fn summaries_of_every_format_parse() {
+        for summary in [
+            "Ignored 7 logs since 1.0s ago, emitting 3 of 10 (30.0%).",
+            "billing: Ignored 7 logs from my_app since 1.0s ago.",
+            r#"{"log_limit":"ignored","count":7,"since_ms":1000}"#,
+            "log_limit=ignored count=7 since=1.0s",
+        ] {
+            assert_eq!(parse_ignored_count(summary), Some(7), "{summary}");
+        }
+        assert_eq!(parse_ignored_count("Hit logging threshold!"), None);
+        assert_eq!(parse_ignored_count(r#"{"log_limit":"threshold"}"#), None);
+    }
+
+    #[cfg(all(
+        feature = "warning-messages",
+        not(any(feature = "json-meta", feature = "logfmt-meta"))
+    ))]
+    #[test]
+    fn a_flood_is_split_into_emitted_and_ignored_logs() {
+        crate::testing_logger::setup();
+        let clock = crate::ManualClock::new();
+        let mut rate_limiter = crate::RateLimiter::with_clock(clock.clone());
+        rate_limiter.set_context("drop-stats".to_owned());
+        let period = std::time::Duration::from_secs(1);
+        for i in 0..10 {
+            rate_limiter.log_maybe(period, 3, || log::info!("drop stats {i}"));
+        }
+        clock.advance(period * 2);
+        rate_limiter.log_maybe(period, 3, || log::info!("drop stats again"));
+
+        crate::testing_logger::validate(|captured_logs| {
+            // Other tests log concurrently, so only this one's logs are looked at.
+            let ours = captured_logs
+                .iter()
+                .filter(|log| log.body.starts_with("drop stats") || log.body.contains("drop-stats"))
+                .map(|log| (log.target.as_str(), log.body.as_str()));
+            let stats = super::drop_stats(ours);
+            assert_eq!(
+                stats.emitted,
+                [
+                    "drop stats 0",
+                    "drop stats 1",
+                    "drop stats 2",
+                    "drop stats again"
+                ]
+            );
+            assert_eq!(stats.meta.len(), 2);
+            assert_eq!(stats.ignored, 7);
+        });
+    }
+}
@@ -14,6 +14,27 @@ pub struct CapturedLog {
     pub body: String,
     /// The level.
     pub level: Level,
+    /// The target.
+    #[allow(dead_code)] // Only read with some combinations of features.
+    pub target: String,
+    #[cfg(all(feature = "warning-messages", feature = "kv"))]
+    /// The structured key-values, formatted.
+    pub key_values: Vec<(String, String)>,
+}
+
+#[cfg(all(feature = "warning-messages", feature = "kv"))]
+struct KeyValueCollector(Vec<(String, String)>);
+
+#[cfg(all(feature = "warning-messages", feature = "kv"))]
+impl<'kvs> log::kv::VisitSource<'kvs> for KeyValueCollector {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        self.0.push((key.to_string(), value.to_string()));
+        Ok(())
+    }
 }
 
 static LOG_RECORDS: Mutex<Vec<CapturedLog>> = Mutex::new(Vec::new());
@@ -28,10 +49,17 @@ impl Log for TestingLogger {
 
     fn log(&self, record: &Record) {
         let mut records = LOG_RECORDS.lock().unwrap();
+        #[cfg(all(feature = "warning-messages", feature = "kv"))]
+        let mut key_values = KeyValueCollector(Vec::new());
+        #[cfg(all(feature = "warning-messages", feature = "kv"))]
+        record.key_values().visit(&mut key_values).unwrap();
         let captured_record = CapturedLog {
             #[cfg(feature = "warning-messages")]
             body: format!("{}", record.args()),
             level: record.level(),
+            target: record.target().to_owned(),
+            #[cfg(all(feature = "warning-messages", feature = "kv"))]
+            key_values: key_values.0,
         };
         records.push(captured_record);
     }
@@ -50,6 +78,8 @@ static TEST_LOGGER: TestingLogger = TestingLogger {};
 /// its thread local storage for a new test.
 pub fn setup() {
     FIRST_TEST.call_once(|| {
+        #[cfg(feature = "warning-messages")]
+        crate::meta::lift_ceiling();
         log::set_logger(&TEST_LOGGER)
             .map(|()| log::set_max_level(LevelFilter::Trace))
             .unwrap();
@@ -0,0 +1,117 @@
+use crate::Clock;
+use crate::KeyedRateLimiter;
+use crate::SynchronisedRateLimiter;
+use crate::SystemClock;
+use std::cell::Cell;
+use std::sync::Mutex;
+use std::thread;
+use std::thread::ThreadId;
+use std::time::Duration;
+
+/// A [`SynchronisedRateLimiter`] whose budget is shared by all threads, but of which each thread
+/// gets a slice of its own, so a single runaway thread can't use up the whole budget. A middle
+/// ground between the thread-local `*_limit!` and the global `*_limit_global!` macros.
+///
+/// Logs within the calling thread's slice are passed on to the shared limiter, which may still
+/// suppress them. They count against the slice either way. The slices are tracked per
+/// [`ThreadId`] behind a lock, for at most `max_threads` threads, evicting the least recently
+/// used as in [`KeyedRateLimiter`].
+pub struct ThreadSlicedLimiter<C: Clock + Clone = SystemClock> {
+    shared: SynchronisedRateLimiter<C>,
+    slices: Mutex<KeyedRateLimiter<ThreadId, C>>,
+}
+
+impl ThreadSlicedLimiter {
+    pub fn new(max_threads: usize) -> Self {
+        Self::with_clock(SystemClock, max_threads)
+    }
+}
+
+impl<C: Clock + Clone> ThreadSlicedLimiter<C> {
+    pub fn with_clock(clock: C, max_threads: usize) -> Self {
+        Self {
+            shared: SynchronisedRateLimiter::with_clock(clock.clone()),
+            slices: Mutex::new(KeyedRateLimiter::with_clock(clock, max_threads)),
+        }
+    }
+
+    /// The limiter holding the shared budget.
+    pub fn shared(&self) -> &SynchronisedRateLimiter<C> {
+        &self.shared
+    }
+
+    /// Limits `log` to `max_per_thread` per `period` for the calling thread, and to
+    /// `max_per_time` per `period` for all threads together.
+    pub fn log_maybe(
+        &self,
+        period: Duration,
+        max_per_time: usize,
+        max_per_thread: usize,
+        log: impl Fn(),
+    ) {
+        let within_slice = Cell::new(false);
+        self.slices.lock().unwrap().log_maybe(
+            thread::current().id(),
+            period,
+            max_per_thread,
+            || within_slice.set(true),
+        );
+        if within_slice.get() {
+            self.shared.log_maybe(period, max_per_time, log);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ThreadSlicedLimiter;
+    use crate::ManualClock;
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn each_thread_gets_a_slice_of_the_shared_budget() {
+        let limiter = ThreadSlicedLimiter::with_clock(ManualClock::new(), 10);
+        let period = Duration::from_secs(60);
+        // A runaway thread and two quiet ones, sharing a budget of 9 in slices of 3.
+        let per_thread: Vec<_> = thread::scope(|scope| {
+            let threads: Vec<_> = [1000, 10, 10]
+                .into_iter()
+                .map(|calls| {
+                    let limiter = &limiter;
+                    scope.spawn(move || {
+                        let emitted = AtomicUsize::new(0);
+                        for _ in 0..calls {
+                            limiter.log_maybe(period, 9, 3, || {
+                                emitted.fetch_add(1, Ordering::Relaxed);
+                            });
+                        }
+                        emitted.into_inner()
+                    })
+                })
+                .collect();
+            threads
+                .into_iter()
+                .map(|thread| thread.join().unwrap())
+                .collect()
+        });
+        assert_eq!(per_thread, [3, 3, 3]);
+
+        // The slices together used up the shared budget, so another thread gets nothing.
+        let emitted = thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let emitted = AtomicUsize::new(0);
+                    limiter.log_maybe(period, 9, 3, || {
+                        emitted.fetch_add(1, Ordering::Relaxed);
+                    });
+                    emitted.into_inner()
+                })
+                .join()
+                .unwrap()
+        });
+        assert_eq!(emitted, 0);
+    }
+}
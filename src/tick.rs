@@ -0,0 +1,118 @@
+/// A fixed window limiter driven by ticks rather than time, e.g. for game loops or fixed-step
+/// simulations. Each window lasts `period` ticks, advanced by calling [`TickLimiter::tick`] or
+/// [`TickLimiter::advance`], and allows `max_per_window` logs.
+///
+/// The ticks can be any monotonic counter, e.g. a frame number or a sequence id, advanced by
+/// however much it moved since the last call.
+pub struct TickLimiter {
+    max_per_window: usize,
+    period: u64,
+    ticks: u64,
+    window_start: u64,
+    count: usize,
+    suppressed: usize,
+}
+
+impl TickLimiter {
+    pub fn new(max_per_window: usize, period: u64) -> Self {
+        Self {
+            max_per_window,
+            period,
+            ticks: 0,
+            window_start: 0,
+            count: 0,
+            suppressed: 0,
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.advance(1);
+    }
+
+    /// Advances by `ticks` at once. A window rolls over once at least `period` ticks passed
+    /// since it started, however far past that the counter jumped.
+    pub fn advance(&mut self, ticks: u64) {
+        self.ticks = self.ticks.saturating_add(ticks);
+    }
+
+    /// The number of ticks so far.
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn log_maybe(&mut self, log: impl Fn()) {
+        if self.ticks - self.window_start >= self.period {
+            #[cfg(feature = "warning-messages")]
+            if self.suppressed > 0 {
+                let ticks = self.ticks - self.window_start;
+                crate::meta::warn_ignored_ticks(
+                    self.suppressed,
+                    ticks,
+                    format_args!(
+                        "Ignored {} logs over the last {ticks} ticks. Starting to log again...",
+                        self.suppressed
+                    ),
+                );
+            }
+            self.window_start = self.ticks;
+            self.count = 0;
+            self.suppressed = 0;
+        }
+
+        if self.count < self.max_per_window {
+            log();
+            self.count += 1;
+            #[cfg(feature = "warning-messages")]
+            if self.count == self.max_per_window {
+                crate::meta::warn_threshold(format_args!(
+                    "Hit logging threshold! Starting to ignore the previous log until tick {}",
+                    self.window_start.saturating_add(self.period)
+                ));
+            }
+        } else {
+            self.suppressed += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TickLimiter;
+    use std::cell::Cell;
+
+    #[test]
+    fn window_rolls_over_on_the_period_tick() {
+        let mut limiter = TickLimiter::new(2, 3);
+        let emitted = Cell::new(0);
+        let mut emissions_per_tick = Vec::new();
+        for _ in 0..7 {
+            emitted.set(0);
+            for _ in 0..5 {
+                limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+            }
+            emissions_per_tick.push(emitted.get());
+            limiter.tick();
+        }
+        // Windows start on ticks 0, 3 and 6.
+        assert_eq!(emissions_per_tick, [2, 0, 0, 2, 0, 0, 2]);
+        assert_eq!(limiter.ticks(), 7);
+    }
+
+    #[test]
+    fn variable_steps_roll_over_past_the_period() {
+        let mut limiter = TickLimiter::new(1, 10);
+        let emitted = Cell::new(0);
+        let mut emissions = Vec::new();
+        for step in [0, 4, 5, 1, 30, 9, 2] {
+            limiter.advance(step);
+            emitted.set(0);
+            for _ in 0..3 {
+                limiter.log_maybe(|| emitted.set(emitted.get() + 1));
+            }
+            emissions.push(emitted.get());
+        }
+        // Windows start on ticks 0, 10, 40 and 51.
+        assert_eq!(emissions, [1, 0, 0, 1, 1, 0, 1]);
+        assert_eq!(limiter.ticks(), 51);
+    }
+}
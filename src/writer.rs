@@ -0,0 +1,168 @@
+use crate::Clock;
+use crate::RateLimiter;
+use crate::SystemClock;
+use std::cell::Cell;
+use std::io;
+use std::io::Write;
+use std::time::Duration;
+
+/// Wraps any writer, dropping lines over a budget of `max_per_time` per `period`.
+///
+/// Each line is one event, so `writeln!` counts once even though it writes in several pieces.
+/// Incomplete lines are held back until their newline arrives or the writer is flushed, which
+/// counts the partial line as an event of its own.
+pub struct RateLimitedWriter<W: Write, C: Clock = SystemClock> {
+    inner: W,
+    rate_limiter: RateLimiter<C>,
+    period: Duration,
+    max_per_time: usize,
+    line: Vec<u8>,
+}
+
+impl<W: Write> RateLimitedWriter<W> {
+    pub fn new(inner: W, max_per_time: usize, period: Duration) -> Self {
+        Self::with_rate_limiter(inner, RateLimiter::new(), max_per_time, period)
+    }
+}
+
+impl<W: Write, C: Clock> RateLimitedWriter<W, C> {
+    pub fn with_rate_limiter(
+        inner: W,
+        rate_limiter: RateLimiter<C>,
+        max_per_time: usize,
+        period: Duration,
+    ) -> Self {
+        Self {
+            inner,
+            rate_limiter,
+            period,
+            max_per_time,
+            line: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Flushes any incomplete line, as [`flush`](Write::flush) would, before unwrapping the writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_line_maybe(&mut self) -> io::Result<()> {
+        let allowed = Cell::new(false);
+        self.rate_limiter
+            .log_maybe(self.period, self.max_per_time, || allowed.set(true));
+        if allowed.get() {
+            self.inner.write_all(&self.line)?;
+        }
+        self.line.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write, C: Clock> Write for RateLimitedWriter<W, C> {
+    /// Lines written before one fails count as written, so the error is only returned if it's the
+    /// first line of `buf` that fails.
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(newline) = rest.iter().position(|&byte| byte == b'\n') {
+            let pending = self.line.len();
+            self.line.extend_from_slice(&rest[..=newline]);
+            if let Err(error) = self.write_line_maybe() {
+                self.line.truncate(pending);
+                return match buf.len() - rest.len() {
+                    0 => Err(error),
+                    written => Ok(written),
+                };
+            }
+            rest = &rest[newline + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            self.write_line_maybe()?;
+        }
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RateLimitedWriter;
+    use crate::ManualClock;
+    use crate::RateLimiter;
+    use std::io;
+    use std::io::Write;
+    use std::time::Duration;
+
+    #[test]
+    fn over_budget_lines_are_dropped() {
+        let clock = ManualClock::new();
+        let rate_limiter = RateLimiter::with_clock(clock.clone());
+        let mut writer = RateLimitedWriter::with_rate_limiter(
+            Vec::new(),
+            rate_limiter,
+            2,
+            Duration::from_secs(1),
+        );
+
+        for i in 0..5 {
+            writeln!(writer, "line {i}").unwrap();
+        }
+        clock.advance(Duration::from_millis(1001));
+        writer.write_all(b"line 5\nline 6\nline ").unwrap();
+        writer.write_all(b"7\n").unwrap();
+        write!(writer, "partial").unwrap();
+        writer.flush().unwrap();
+
+        assert_eq!(
+            String::from_utf8(writer.into_inner().unwrap()).unwrap(),
+            "line 0\nline 1\nline 5\nline 6\n"
+        );
+    }
+
+    /// Accepts `capacity` whole writes, failing any after.
+    struct Failing {
+        written: Vec<u8>,
+        capacity: usize,
+    }
+
+    impl Write for Failing {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.capacity == 0 {
+                return Err(io::Error::other("full"));
+            }
+            self.capacity -= 1;
+            self.written.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn a_failing_line_after_written_ones_is_not_consumed() {
+        let failing = Failing {
+            written: Vec::new(),
+            capacity: 1,
+        };
+        let mut writer = RateLimitedWriter::new(failing, 10, Duration::from_secs(1));
+
+        assert_eq!(writer.write(b"one\ntwo\n").unwrap(), 4);
+        assert!(writer.write(b"two\n").is_err());
+        writer.inner.capacity = 2;
+        assert_eq!(writer.write(b"two\nthr").unwrap(), 7);
+        writer.write_all(b"ee").unwrap();
+
+        let failing = writer.into_inner().unwrap();
+        assert_eq!(failing.written, b"one\ntwo\nthree");
+    }
+}
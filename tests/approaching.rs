@@ -0,0 +1,58 @@
+//! Checks that `set_macro_config` applies `approaching_percent` to the global macros' and the
+//! policies' limiters, in a binary of its own as the config, the policies and the logger capturing
+//! the notices are process-wide.
+#![cfg(all(
+    feature = "warning-messages",
+    not(any(feature = "json-meta", feature = "logfmt-meta"))
+))]
+
+use log_limit::policy;
+use log_limit::LimiterConfig;
+use std::sync::Mutex;
+use std::time::Duration;
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LOGGED.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+fn notices() -> usize {
+    LOGGED
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|body| body.starts_with("Used up 50%"))
+        .count()
+}
+
+#[test]
+fn global_macros_and_policies_notice_approaching_the_threshold() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log_limit::set_macro_config(LimiterConfig {
+        approaching_percent: Some(50),
+        ..LimiterConfig::default()
+    });
+    for _ in 0..10 {
+        log_limit::info_limit_global!(4, Duration::from_secs(60), "global");
+    }
+    assert_eq!(notices(), 1);
+
+    policy::set_limit(module_path!(), None, 4, Duration::from_secs(60));
+    for _ in 0..10 {
+        log_limit::info_limit!(100, Duration::from_secs(60), "policy");
+    }
+    policy::clear_limits();
+    assert_eq!(notices(), 2);
+}
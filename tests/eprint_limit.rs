@@ -0,0 +1,37 @@
+//! Checks what `eprint_limit!` prints to stderr, by running this test again in a child process
+//! and capturing its stderr, as the test harness doesn't capture `eprintln!` for inspection.
+
+use std::cell::Cell;
+use std::process::Command;
+use std::time::Duration;
+
+/// Set for the child process, which prints rather than checks.
+const CHILD: &str = "LOG_LIMIT_EPRINT_CHILD";
+
+#[test]
+fn eprint_limit_throttles_stderr() {
+    if std::env::var_os(CHILD).is_some() {
+        let evaluated = Cell::new(0);
+        for _ in 0..10 {
+            // The arguments are only evaluated when printing.
+            log_limit::eprint_limit!(3, Duration::from_secs(60), "stderr {}", {
+                evaluated.set(evaluated.get() + 1);
+                evaluated.get()
+            });
+        }
+        return;
+    }
+
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "eprint_limit_throttles_stderr", "--nocapture"])
+        .env(CHILD, "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let printed: Vec<_> = stderr
+        .lines()
+        .filter(|line| line.starts_with("stderr"))
+        .collect();
+    assert_eq!(printed, ["stderr 1", "stderr 2", "stderr 3"], "{stderr}");
+}
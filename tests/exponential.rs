@@ -0,0 +1,50 @@
+//! Checks that `log_exponential!` doesn't count calls at a filtered out level, in a binary of its
+//! own so it can set the max level.
+
+use std::sync::Mutex;
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CollectingLogger;
+
+impl log::Log for CollectingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() != "log_limit"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            LOGGED.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn retry(attempt: usize) {
+    log_limit::log_exponential!(log::Level::Debug, "Retrying, attempt {attempt}");
+}
+
+#[test]
+fn filtered_out_levels_do_not_advance_the_count() {
+    log::set_logger(&CollectingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    for attempt in 0..3 {
+        retry(attempt);
+    }
+    assert!(LOGGED.lock().unwrap().is_empty());
+
+    // Counting restarts from the first enabled call rather than the fourth.
+    log::set_max_level(log::LevelFilter::Debug);
+    for attempt in 3..7 {
+        retry(attempt);
+    }
+    assert_eq!(
+        *LOGGED.lock().unwrap(),
+        [
+            "Retrying, attempt 3",
+            "Retrying, attempt 4",
+            "Retrying, attempt 6"
+        ]
+    );
+}
@@ -0,0 +1,77 @@
+//! Checks that the last suppressed log is emitted from its call site by the macros configured with
+//! `set_macro_config` and by `RateLimiter::log_message`, in a binary of its own as the config and
+//! the logger capturing the logs are process-wide.
+
+use log_limit::LimiterConfig;
+use log_limit::LogContext;
+use log_limit::RateLimiter;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+/// The body, file and line of each log.
+type Logged = (String, Option<String>, Option<u32>);
+
+static LOGGED: Mutex<Vec<Logged>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LOGGED.lock().unwrap().push((
+            record.args().to_string(),
+            record.file().map(str::to_owned),
+            record.line(),
+        ));
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn the_last_suppressed_log_is_emitted_from_its_call_site() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    let config = LimiterConfig {
+        emit_last_suppressed: true,
+        ..LimiterConfig::default()
+    };
+    log_limit::set_macro_config(config);
+    let period = Duration::from_millis(50);
+    let mut rate_limiter = RateLimiter::with_config(config);
+    let context = LogContext {
+        level: log::Level::Info,
+        target: "last_suppressed",
+    };
+    let (macro_line, method_line) = (line!() + 6, line!() + 7);
+    // The last call of each rolls its window over.
+    for i in 0..5 {
+        if i == 4 {
+            thread::sleep(period * 2);
+        }
+        log_limit::info_limit!(2, period, "macro {i}");
+        rate_limiter.log_message(period, 2, &context, format_args!("method {i}"));
+    }
+
+    let logged = LOGGED.lock().unwrap();
+    for (body, line) in [
+        ("macro 1", macro_line),
+        ("macro 3", macro_line),
+        ("method 1", method_line),
+        ("method 3", method_line),
+    ] {
+        let log = logged.iter().find(|log| log.0 == body);
+        assert_eq!(
+            log,
+            Some(&(body.to_owned(), Some(file!().to_owned()), Some(line))),
+            "{logged:?}"
+        );
+    }
+    assert!(!logged
+        .iter()
+        .any(|log| log.0 == "macro 2" || log.0 == "method 2"));
+}
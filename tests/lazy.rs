@@ -0,0 +1,58 @@
+//! Checks that `lazy_limit!` neither builds its message nor uses up its budget for a filtered out
+//! level, in a binary of its own so it can set the max level.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+static BUILT: AtomicUsize = AtomicUsize::new(0);
+
+struct CollectingLogger;
+
+impl log::Log for CollectingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() != "log_limit"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            LOGGED.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn expensive_message() -> String {
+    BUILT.fetch_add(1, Ordering::Relaxed);
+    "pool state".to_owned()
+}
+
+fn log_pool_state() {
+    log_limit::lazy_limit!(
+        log::Level::Debug,
+        1,
+        Duration::from_secs(60),
+        expensive_message()
+    );
+}
+
+#[test]
+fn filtered_out_levels_neither_build_the_message_nor_use_up_the_budget() {
+    log::set_logger(&CollectingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    for _ in 0..5 {
+        log_pool_state();
+    }
+    assert_eq!(BUILT.load(Ordering::Relaxed), 0);
+    assert!(LOGGED.lock().unwrap().is_empty());
+
+    // The budget of 1 is still unused, and the suppressed call doesn't build the message either.
+    log::set_max_level(log::LevelFilter::Debug);
+    log_pool_state();
+    log_pool_state();
+    assert_eq!(BUILT.load(Ordering::Relaxed), 1);
+    assert_eq!(*LOGGED.lock().unwrap(), ["pool state"]);
+}
@@ -0,0 +1,45 @@
+//! Checks that `set_macro_config` reaches the thread-local macros' limiters, in a binary of its own
+//! as the config and the logger capturing the hint are process-wide.
+#![cfg(feature = "warning-messages")]
+
+use log_limit::LimiterConfig;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        LOGGED.lock().unwrap().push(record.args().to_string());
+    }
+
+    fn flush(&self) {}
+}
+
+#[test]
+fn macros_hint_at_too_short_periods_once_configured() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    log_limit::set_macro_config(LimiterConfig {
+        hint_misconfiguration: true,
+        ..LimiterConfig::default()
+    });
+    for _ in 0..10 {
+        log_limit::info_limit!(5, Duration::from_millis(1), "polled");
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    let logged = LOGGED.lock().unwrap();
+    let hints = logged
+        .iter()
+        .filter(|body| body.contains("single call") || body.contains("misconfigured"))
+        .count();
+    assert_eq!(hints, 1, "{logged:?}");
+}
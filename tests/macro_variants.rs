@@ -0,0 +1,52 @@
+//! Checks that the thread-local and global macros keep separate budgets even when used for the
+//! same event, in a binary of its own so it can install a counting logger.
+
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct CollectingLogger;
+
+impl log::Log for CollectingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() != "log_limit"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            LOGGED.lock().unwrap().push(record.args().to_string());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn log_event() {
+    log_limit::info_limit!(2, Duration::from_secs(60), "thread-local");
+    log_limit::info_limit_global!(2, Duration::from_secs(60), "global");
+}
+
+#[test]
+fn thread_local_budgets_are_per_thread_and_global_ones_per_process() {
+    log::set_logger(&CollectingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    thread::scope(|scope| {
+        for _ in 0..3 {
+            scope.spawn(|| {
+                for _ in 0..5 {
+                    log_event();
+                }
+            });
+        }
+    });
+
+    let logged = LOGGED.lock().unwrap();
+    let count = |message| logged.iter().filter(|logged| *logged == message).count();
+    // Each of the 3 threads has a budget of its own for the thread-local call site, while the
+    // global call site's budget is shared by all of them, and neither draws from the other's.
+    assert_eq!(count("thread-local"), 6);
+    assert_eq!(count("global"), 2);
+}
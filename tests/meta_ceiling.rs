@@ -0,0 +1,91 @@
+//! Checks that the limiters' own meta messages stay under the ceiling however often windows roll
+//! over, and that those dropped are reported, in a binary of its own as the ceiling is
+//! process-wide.
+#![cfg(feature = "warning-messages")]
+
+use log_limit::ManualClock;
+use log_limit::RateLimiter;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+
+static META_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+static LAST_META_MESSAGE: Mutex<String> = Mutex::new(String::new());
+
+struct CountingLogger;
+
+impl log::Log for CountingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        if record.target() == "log_limit" {
+            META_MESSAGES.fetch_add(1, Ordering::Relaxed);
+            *LAST_META_MESSAGE.lock().unwrap() = record.args().to_string();
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// How a meta message reports `dropped` earlier ones, at its end.
+fn reported(dropped: usize) -> String {
+    if cfg!(feature = "json-meta") {
+        format!(r#","dropped":{dropped}}}"#)
+    } else if cfg!(feature = "logfmt-meta") {
+        format!(" dropped={dropped}")
+    } else {
+        format!(" ({dropped} earlier meta messages were dropped over the ceiling)")
+    }
+}
+
+#[test]
+fn meta_messages_stay_under_the_ceiling_during_rapid_rollovers() {
+    log::set_logger(&CountingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let start = Instant::now();
+    let clock = ManualClock::new();
+    let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+    // Every window rolling over logs a summary, 1000 meta messages without the ceiling.
+    for _ in 0..1000 {
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_millis(1), 1, || {});
+        }
+        clock.advance(Duration::from_millis(2));
+    }
+    let elapsed = start.elapsed();
+
+    // At most 5 per second, in as many ceiling windows as started within the elapsed time.
+    let ceiling = 5 * (elapsed.as_secs() as usize + 1);
+    let meta_messages = META_MESSAGES.load(Ordering::Relaxed);
+    assert!(
+        (1..=ceiling).contains(&meta_messages),
+        "{meta_messages} meta messages in {elapsed:?}"
+    );
+
+    // The next one admitted reports those dropped so far.
+    log_limit::set_meta_ceiling(usize::MAX, Duration::from_secs(3600));
+    rate_limiter.log_maybe(Duration::from_millis(1), 1, || {});
+    let last = LAST_META_MESSAGE.lock().unwrap().clone();
+    assert!(last.contains("dropped"), "{last}");
+
+    // With no meta messages allowed, the threshold and the 9 summaries of 10 windows are dropped.
+    log_limit::set_meta_ceiling(0, Duration::from_secs(3600));
+    let before = META_MESSAGES.load(Ordering::Relaxed);
+    let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+    for _ in 0..10 {
+        for _ in 0..3 {
+            rate_limiter.log_maybe(Duration::from_millis(1), 1, || {});
+        }
+        clock.advance(Duration::from_millis(2));
+    }
+    assert_eq!(META_MESSAGES.load(Ordering::Relaxed), before);
+    log_limit::set_meta_ceiling(usize::MAX, Duration::from_secs(3600));
+    rate_limiter.log_maybe(Duration::from_millis(1), 1, || {});
+    let last = LAST_META_MESSAGE.lock().unwrap();
+    assert!(last.ends_with(&reported(10)), "{last}");
+}
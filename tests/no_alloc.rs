@@ -0,0 +1,80 @@
+//! Checks that the limiters don't allocate on the hot path, using a global allocator that counts
+//! the allocations made by the current thread.
+
+use log_limit::ManualClock;
+use log_limit::RateLimiter;
+use log_limit::SynchronisedRateLimiter;
+use std::alloc::GlobalAlloc;
+use std::alloc::Layout;
+use std::alloc::System;
+use std::cell::Cell;
+use std::time::Duration;
+
+struct CountingAllocator;
+
+thread_local! {
+    static COUNTING: Cell<bool> = const { Cell::new(false) };
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if COUNTING.with(Cell::get) {
+            ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        }
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Enables the threshold and ignored-count warnings without allocating to write them.
+struct NopLogger;
+
+impl log::Log for NopLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, _: &log::Record) {}
+
+    fn flush(&self) {}
+}
+
+fn allocations_during(f: impl FnOnce()) -> usize {
+    ALLOCATIONS.with(|allocations| allocations.set(0));
+    COUNTING.with(|counting| counting.set(true));
+    f();
+    COUNTING.with(|counting| counting.set(false));
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn limiters_do_not_allocate() {
+    log::set_logger(&NopLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+    let period = Duration::from_secs(1);
+    let clock = ManualClock::new();
+    let mut rate_limiter = RateLimiter::with_clock(clock.clone());
+    let synchronised_rate_limiter = SynchronisedRateLimiter::with_clock(clock.clone());
+    let emitted = Cell::new(0);
+    let log = || emitted.set(emitted.get() + 1);
+
+    // Emitting, hitting the threshold, suppressing and rolling over.
+    let allocations = allocations_during(|| {
+        for _ in 0..2 {
+            for _ in 0..10 {
+                rate_limiter.log_maybe(period, 5, log);
+                synchronised_rate_limiter.log_maybe(period, 5, log);
+            }
+            clock.advance(period * 2);
+        }
+    });
+    assert_eq!(emitted.get(), 20);
+    assert_eq!(allocations, 0);
+}
@@ -0,0 +1,42 @@
+//! Checks that logging before a logger is installed doesn't use up any budget, in a binary of its
+//! own as the logger can only be installed once per process.
+
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+static LOGGED: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingLogger;
+
+impl log::Log for CountingLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() != "log_limit"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            LOGGED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn log_startup() -> usize {
+    log_limit::info_limit_global!(2, Duration::from_secs(60), "Starting up");
+    log_limit::info_limit_counted!(2, Duration::from_secs(60), "Starting up")
+}
+
+#[test]
+fn logs_before_the_logger_is_installed_use_no_budget() {
+    for _ in 0..10 {
+        assert_eq!(log_startup(), 0);
+    }
+
+    log::set_logger(&CountingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    let suppressed: Vec<_> = (0..4).map(|_| log_startup()).collect();
+    assert_eq!(suppressed, [0, 0, 1, 2]);
+    assert_eq!(LOGGED.load(Ordering::Relaxed), 4);
+}
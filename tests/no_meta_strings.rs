@@ -0,0 +1,69 @@
+//! Checks that without the `warning-messages` feature none of the meta-messages make it into the
+//! binary, neither their wording nor the functions logging them, by searching this test's own
+//! executable for them after using every limiter that has any.
+#![cfg(not(feature = "warning-messages"))]
+
+use log_limit::CardinalityLimiter;
+use log_limit::FixedLimiter;
+use log_limit::HybridLimiter;
+use log_limit::LeakyBucketLimiter;
+use log_limit::Rate;
+use log_limit::RateLimiter;
+use log_limit::TickLimiter;
+use std::time::Duration;
+
+fn use_every_limiter() {
+    let period = Duration::from_millis(1);
+    let mut rate_limiter = RateLimiter::new();
+    let mut fixed = FixedLimiter::<1>::new(period);
+    let mut hybrid = HybridLimiter::new(1, period);
+    let mut leaky = LeakyBucketLimiter::new(1, Rate::new(1, period));
+    let mut cardinality = CardinalityLimiter::new(1, period);
+    let mut tick = TickLimiter::new(1, 1);
+    for i in 0..3 {
+        rate_limiter.log_maybe(period, 1, || {});
+        rate_limiter.poll();
+        fixed.log_maybe(|| {});
+        hybrid.log_maybe(|| {});
+        leaky.log_maybe(|| {});
+        cardinality.log_maybe(i, || {});
+        tick.tick();
+        tick.log_maybe(|| {});
+    }
+    log_limit::info_limit!(1, period, "Limited");
+    log_limit::info_limit_global!(1, period, "Limited");
+}
+
+/// Whether this test's executable contains `reversed`, reversed so that the needles themselves
+/// don't put it into the binary.
+fn binary_contains(binary: &[u8], reversed: &str) -> Option<String> {
+    let needle: String = reversed.chars().rev().collect();
+    binary
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+        .then_some(needle)
+}
+
+#[test]
+fn meta_message_wording_is_compiled_out() {
+    use_every_limiter();
+    let binary = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    for reversed in [
+        "dlohserht gniggol",
+        "niaga gol ot gnitratS",
+        "ces/sgol",
+        "lluf tekcuB",
+    ] {
+        let found = binary_contains(&binary, reversed);
+        assert!(found.is_none(), "{found:?} is in the binary");
+    }
+}
+
+#[test]
+fn meta_functions_are_not_linked() {
+    use_every_limiter();
+    let binary = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    // Every function of the `meta` module is mangled with its path, as `9log_limit4meta`.
+    let found = binary_contains(&binary, "atem4timil_gol9");
+    assert!(found.is_none(), "{found:?} is in the binary");
+}
@@ -0,0 +1,64 @@
+//! Checks the overflow sink's rate bound and that it may log or panic itself, in a binary of its
+//! own as the sink and the logger are process-wide.
+
+use log_limit::buffer;
+use log_limit::ManualClock;
+use std::panic;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+
+struct NoopLogger;
+
+impl log::Log for NoopLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, _record: &log::Record) {}
+
+    fn flush(&self) {}
+}
+
+fn flood(count: usize) {
+    for i in 0..count {
+        log_limit::info_limit!(1, Duration::from_secs(3600), "overflow {i}");
+    }
+}
+
+#[test]
+fn the_sink_is_bounded_and_called_outside_the_lock() {
+    log::set_logger(&NoopLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    let clock = ManualClock::new();
+    let overflowed = Arc::new(Mutex::new(Vec::new()));
+    let sink = overflowed.clone();
+    buffer::set_overflow_sink_with_clock(
+        clock.clone(),
+        2,
+        Duration::from_secs(1),
+        move |message| {
+            sink.lock().unwrap().push(message.to_owned());
+            // Suppressed too, so this reaches the sink again, which must not deadlock.
+            log_limit::warn_limit!(1, Duration::from_secs(3600), "sink saw {message}");
+        },
+    );
+
+    flood(6);
+    assert_eq!(overflowed.lock().unwrap().len(), 2);
+    clock.advance(Duration::from_millis(1500));
+    flood(6);
+    assert_eq!(overflowed.lock().unwrap().len(), 4);
+
+    buffer::set_overflow_sink(10, Duration::from_secs(1), |_| panic!("the sink broke"));
+    assert!(panic::catch_unwind(|| flood(3)).is_err());
+    // A panicking sink doesn't poison the sink's lock.
+    let replaced = Arc::new(Mutex::new(0));
+    let sink = replaced.clone();
+    buffer::set_overflow_sink(10, Duration::from_secs(1), move |_| {
+        *sink.lock().unwrap() += 1;
+    });
+    flood(3);
+    assert_eq!(*replaced.lock().unwrap(), 3);
+    buffer::clear_overflow_sink();
+}
@@ -0,0 +1,46 @@
+//! Checks that a logger logging through a rate-limited call site from within its own `log` doesn't
+//! panic, in a binary of its own so it can install such a logger.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct ReentrantLogger;
+
+impl log::Log for ReentrantLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.target() != "log_limit"
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let message = record.args().to_string();
+        LOGGED.lock().unwrap().push(message.clone());
+        // E.g. a logger reporting its own failures through the application's logging.
+        if message.starts_with("outer") {
+            log_through_call_site("inner");
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+fn log_through_call_site(message: &str) {
+    log_limit::info_limit!(10, Duration::from_secs(60), "{message}");
+}
+
+#[test]
+fn logging_reentrantly_through_a_call_site_drops_the_nested_log() {
+    log::set_logger(&ReentrantLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+
+    log_through_call_site("outer 1");
+    log_through_call_site("outer 2");
+
+    // The nested logs find the limiter in use and are dropped, and the limiter is usable again
+    // afterwards.
+    assert_eq!(*LOGGED.lock().unwrap(), ["outer 1", "outer 2"]);
+}
@@ -0,0 +1,53 @@
+#![cfg(all(feature = "shared-memory", unix))]
+
+use log_limit::SharedMemoryLimiter;
+use std::ptr;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+#[test]
+fn forked_processes_share_the_budget() {
+    let limiter = SharedMemoryLimiter::new().unwrap();
+    // Counts the emitted logs across processes.
+    let emitted = unsafe {
+        let mapping = libc::mmap(
+            ptr::null_mut(),
+            size_of::<AtomicUsize>(),
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        );
+        assert_ne!(mapping, libc::MAP_FAILED);
+        &*mapping.cast::<AtomicUsize>()
+    };
+    let log_all = || {
+        for _ in 0..100 {
+            limiter.log_maybe(Duration::from_secs(3600), 10, || {
+                emitted.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+    };
+
+    let children: Vec<_> = (0..4)
+        .map(|_| match unsafe { libc::fork() } {
+            0 => {
+                log_all();
+                unsafe { libc::_exit(0) };
+            }
+            pid => {
+                assert!(pid > 0);
+                pid
+            }
+        })
+        .collect();
+    log_all();
+    for child in children {
+        let mut status = 0;
+        assert_eq!(unsafe { libc::waitpid(child, &mut status, 0) }, child);
+        assert!(libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0);
+    }
+
+    assert_eq!(emitted.load(Ordering::Relaxed), 10);
+}
@@ -0,0 +1,79 @@
+//! Checks that floods across several call sites are coalesced into a single storm notice, in a
+//! binary of its own as the detector and the logger capturing the notice are process-wide.
+#![cfg(feature = "registry")]
+
+use log_limit::storm;
+use log_limit::Rate;
+use std::sync::Mutex;
+use std::time::Duration;
+
+static LOGGED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+struct CapturingLogger;
+
+impl log::Log for CapturingLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        let entry = (record.target().to_owned(), record.args().to_string());
+        LOGGED.lock().unwrap().push(entry);
+    }
+
+    fn flush(&self) {}
+}
+
+fn emitted(message: &str) -> usize {
+    let logged = LOGGED.lock().unwrap();
+    logged.iter().filter(|(_, body)| body == message).count()
+}
+
+#[test]
+fn floods_across_call_sites_give_a_single_storm_notice() {
+    log::set_logger(&CapturingLogger).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    storm::set_detector(
+        Rate::new(20, Duration::from_secs(60)),
+        Duration::from_secs(60),
+        10,
+    );
+
+    // Each site stays within its own budget for a while, but together they pass the threshold.
+    for _ in 0..100 {
+        log_limit::error_limit!(10, Duration::from_secs(60), "Connection lost");
+        log_limit::warn_limit_global!(10, Duration::from_secs(60), "Retrying");
+        log_limit::info_limit!(10, Duration::from_secs(60), "Queue stalled");
+    }
+    assert!(storm::is_storming());
+    // The sites' budgets were cut to 1 when the 21st log was emitted.
+    let per_site = ["Connection lost", "Retrying", "Queue stalled"].map(emitted);
+    assert_eq!(per_site, [7, 7, 7]);
+
+    #[cfg(feature = "warning-messages")]
+    {
+        let logged = LOGGED.lock().unwrap();
+        let notices: Vec<_> = logged
+            .iter()
+            .filter(|(target, body)| target == "log_limit" && body.contains("storm"))
+            .collect();
+        assert_eq!(notices.len(), 1, "{notices:?}");
+        // Worded plainly, or structured with the `json-meta` or `logfmt-meta` feature.
+        let notice = &notices[0].1;
+        assert!(
+            ["from 3 call sites", r#""call_sites":3"#, "call_sites=3"]
+                .iter()
+                .any(|wording| notice.contains(wording)),
+            "{notice}"
+        );
+    }
+
+    // A site joining in during the storm only gets the cut budget.
+    for _ in 0..10 {
+        log_limit::info_limit!(10, Duration::from_secs(60), "Cache miss");
+    }
+    assert_eq!(emitted("Cache miss"), 1);
+
+    storm::clear_detector();
+    assert!(!storm::is_storming());
+}